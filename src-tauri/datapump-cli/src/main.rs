@@ -0,0 +1,796 @@
+// Headless command-line front end for the datapump migration engine.
+//
+// Exit codes:
+//   0   success
+//   1   the command failed (bad input, connection error, failed row, ...)
+//   2   invalid command-line usage
+//   130 the migration was cancelled with Ctrl-C
+
+use clap::{Args, Parser, Subcommand};
+use datapump_core::checkpoint::{self, Checkpoint};
+use datapump_core::compression::Compression;
+use datapump_core::constraints::{Constraints, SuggestedConstraints};
+use datapump_core::csv_schema::{Sample, DEFAULT_SAMPLE_ROWS};
+use datapump_core::dialect::{Dialect, DialectOptions};
+use datapump_core::error::{Context, DatapumpError};
+use datapump_core::fixed_width::{self, FixedWidthColumn};
+use datapump_core::headers::HeaderPolicy;
+use datapump_core::migrate::{self, LoadMode, SinkConfig, SourceConfig};
+use datapump_core::progress::StderrProgress;
+use datapump_core::query::{self, QueryResult};
+use datapump_core::ragged::{RaggedRowPolicy, RaggedRows};
+use datapump_core::schema::Schema;
+use datapump_core::spec::JobSpec;
+use datapump_core::{archive, csv_schema, jobs, postgres_copy};
+use std::process::ExitCode;
+
+const EXIT_FAILURE: u8 = 1;
+const EXIT_CANCELLED: u8 = 130;
+
+#[derive(Parser)]
+#[command(
+    name = "datapump",
+    version,
+    about = "Load CSV/TSV files into SQLite or PostgreSQL"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// How to read a CSV/TSV file, where detection should not decide.
+#[derive(Args)]
+struct CsvOptions {
+    /// Character encoding, e.g. "windows-1252"; detected when omitted
+    #[arg(long)]
+    encoding: Option<String>,
+    /// Field delimiter; detected when omitted
+    #[arg(long)]
+    delimiter: Option<char>,
+    /// Whether the first row holds column names; detected when omitted
+    #[arg(long)]
+    header: Option<bool>,
+    /// Lines to skip before the header row; detected when omitted
+    #[arg(long)]
+    skip_lines: Option<usize>,
+    /// Lines to drop from the end of the file, such as totals
+    #[arg(long, default_value_t = 0)]
+    skip_footer: usize,
+    /// Ignore lines starting with this prefix, e.g. "#"
+    #[arg(long)]
+    comment: Option<String>,
+    /// Ignore lines matching this regular expression
+    #[arg(long)]
+    comment_pattern: Option<String>,
+    /// Decimal separator of numbers, '.' or ','; detected per column when
+    /// omitted
+    #[arg(long)]
+    decimal: Option<char>,
+    /// Turn header names into snake_case column names
+    #[arg(long)]
+    snake_case: bool,
+}
+
+impl CsvOptions {
+    fn policy(&self) -> HeaderPolicy {
+        HeaderPolicy {
+            snake_case: self.snake_case,
+        }
+    }
+
+    fn options(&self) -> DialectOptions {
+        DialectOptions {
+            encoding: self.encoding.clone(),
+            delimiter: self.delimiter,
+            has_header: self.header,
+            skip_lines: self.skip_lines,
+            skip_footer: self.skip_footer,
+            comment: self.comment.clone(),
+            comment_pattern: self.comment_pattern.clone(),
+            decimal: self.decimal,
+        }
+    }
+
+    /// Sniff the file's dialect, keeping what was given on the command line.
+    fn dialect(&self, file: &str) -> Result<Dialect, DatapumpError> {
+        Dialect::sniff(file, &self.options())
+    }
+
+    fn source(&self, path: String) -> SourceConfig {
+        SourceConfig::Csv {
+            path,
+            dialect: self.options(),
+            headers: self.policy(),
+        }
+    }
+
+    /// A source for one file, or for several loaded into one table.
+    fn files_source(&self, files: Vec<String>, source_file_column: bool) -> SourceConfig {
+        SourceConfig::csv_files(files, self.options(), self.policy(), source_file_column)
+    }
+}
+
+/// Which rows column types are inferred from.
+#[derive(Args)]
+struct SampleOptions {
+    #[arg(long, value_enum, default_value_t = SampleMethod::First)]
+    sample: SampleMethod,
+    /// Rows to sample, for the first and reservoir methods
+    #[arg(long, default_value_t = DEFAULT_SAMPLE_ROWS)]
+    sample_rows: usize,
+}
+
+impl SampleOptions {
+    fn sample(&self) -> Sample {
+        let rows = self.sample_rows;
+        match self.sample {
+            SampleMethod::First => Sample::First { rows },
+            SampleMethod::Reservoir => Sample::Reservoir { rows },
+            SampleMethod::Full => Sample::Full,
+        }
+    }
+}
+
+/// Constraints a new table is created with; `get-csv-schema` suggests some.
+#[derive(Args)]
+struct ConstraintOptions {
+    /// Columns that may not be NULL, separated by commas
+    #[arg(long, value_delimiter = ',')]
+    not_null: Vec<String>,
+    /// Columns of the primary key, separated by commas
+    #[arg(long, value_delimiter = ',')]
+    primary_key: Vec<String>,
+}
+
+impl ConstraintOptions {
+    fn constraints(&self) -> Constraints {
+        Constraints {
+            not_null: self.not_null.clone(),
+            primary_key: self.primary_key.clone(),
+        }
+    }
+}
+
+/// What to do with rows that have more or fewer fields than the header.
+#[derive(Args)]
+struct RaggedOptions {
+    #[arg(long, value_enum, default_value_t = Ragged::Error)]
+    ragged_rows: Ragged,
+    /// CSV file rejected rows are appended to, after their file and line
+    #[arg(long)]
+    reject_file: Option<String>,
+}
+
+impl RaggedOptions {
+    fn policy(&self) -> RaggedRowPolicy {
+        RaggedRowPolicy {
+            ragged_rows: self.ragged_rows.into(),
+            reject_file: self.reject_file.clone(),
+        }
+    }
+}
+
+/// Where the columns of a fixed-width file are; detected when not given.
+#[derive(Args)]
+struct FixedWidthOptions {
+    /// Column as name:start:length[:type], counting from 1; may be repeated
+    #[arg(long = "column", conflicts_with = "columns_file")]
+    columns: Vec<String>,
+    /// JSON, TOML or text file listing the columns
+    #[arg(long)]
+    columns_file: Option<String>,
+}
+
+impl FixedWidthOptions {
+    fn columns(&self) -> Result<Vec<FixedWidthColumn>, DatapumpError> {
+        match &self.columns_file {
+            Some(path) => fixed_width::load_columns(path),
+            None => self
+                .columns
+                .iter()
+                .map(|column| FixedWidthColumn::parse(column))
+                .collect(),
+        }
+    }
+
+    fn source(&self, path: String, csv: &CsvOptions) -> Result<SourceConfig, DatapumpError> {
+        let columns = match self.columns_file {
+            Some(_) => Vec::new(),
+            None => self.columns()?,
+        };
+        Ok(SourceConfig::FixedWidth {
+            path,
+            columns,
+            columns_file: self.columns_file.clone(),
+            dialect: csv.options(),
+            headers: csv.policy(),
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the inferred schema of a CSV/TSV file as JSON, which --schema
+    /// accepts, edited or as it is
+    GetCsvSchema {
+        file: String,
+        #[command(flatten)]
+        csv: CsvOptions,
+        #[command(flatten)]
+        sample: SampleOptions,
+    },
+    /// Load CSV/TSV files with the same columns into a SQLite table
+    CsvToSqlite {
+        /// File or glob pattern, e.g. "sales_*.csv"; may be repeated
+        #[arg(long = "file", required = true)]
+        files: Vec<String>,
+        /// Add a _source_file column with the file each row came from
+        #[arg(long)]
+        source_file_column: bool,
+        /// Path to the SQLite database
+        #[arg(long)]
+        db: String,
+        #[arg(long)]
+        table: String,
+        /// Schema as JSON or a file holding it; inferred from the file when
+        /// omitted
+        #[arg(long, value_parser = parse_schema_arg)]
+        schema: Option<Schema>,
+        #[command(flatten)]
+        csv: CsvOptions,
+        #[command(flatten)]
+        sample: SampleOptions,
+        #[command(flatten)]
+        ragged: RaggedOptions,
+        #[command(flatten)]
+        constraints: ConstraintOptions,
+        #[arg(long, default_value_t = 10_000)]
+        batch_size: usize,
+    },
+    /// Load CSV/TSV files with the same columns into a PostgreSQL table
+    CsvToPostgres {
+        /// File or glob pattern, e.g. "sales_*.csv"; may be repeated
+        #[arg(long = "file", required = true)]
+        files: Vec<String>,
+        /// Add a _source_file column with the file each row came from
+        #[arg(long)]
+        source_file_column: bool,
+        /// PostgreSQL connection string
+        #[arg(long)]
+        connection: String,
+        #[arg(long)]
+        table: String,
+        /// Schema as JSON or a file holding it; inferred from the file when
+        /// omitted
+        #[arg(long, value_parser = parse_schema_arg)]
+        schema: Option<Schema>,
+        #[command(flatten)]
+        csv: CsvOptions,
+        #[command(flatten)]
+        sample: SampleOptions,
+        #[command(flatten)]
+        ragged: RaggedOptions,
+        #[command(flatten)]
+        constraints: ConstraintOptions,
+        #[arg(long, default_value_t = 10_000)]
+        batch_size: usize,
+    },
+    /// Load a CSV/TSV file into PostgreSQL with the binary COPY fast path
+    ImportCsvToPostgres {
+        #[arg(long)]
+        file: String,
+        /// PostgreSQL connection string
+        #[arg(long)]
+        connection: String,
+        #[arg(long)]
+        table: String,
+        #[command(flatten)]
+        csv: CsvOptions,
+        #[command(flatten)]
+        ragged: RaggedOptions,
+        /// Schema as JSON or a file holding it; inferred from the file when
+        /// omitted
+        #[arg(long, value_parser = parse_schema_arg)]
+        schema: Option<Schema>,
+        #[command(flatten)]
+        sample: SampleOptions,
+    },
+    /// List the files in a zip archive with the table each would get, followed
+    /// by its columns, one tab-separated name and type per line
+    ListZip {
+        file: String,
+        #[command(flatten)]
+        csv: CsvOptions,
+    },
+    /// Load files from a zip archive into one table each, without extracting it
+    ImportZip {
+        #[arg(long)]
+        file: String,
+        /// Archive member to load; may be repeated. All supported files when omitted
+        #[arg(long = "member")]
+        members: Vec<String>,
+        /// Path to a SQLite database to load into
+        #[arg(
+            long,
+            required_unless_present = "connection",
+            conflicts_with = "connection"
+        )]
+        db: Option<String>,
+        /// PostgreSQL connection string to load into
+        #[arg(long)]
+        connection: Option<String>,
+        #[command(flatten)]
+        csv: CsvOptions,
+        #[command(flatten)]
+        ragged: RaggedOptions,
+        #[arg(long, value_enum, default_value_t = Mode::Append)]
+        mode: Mode,
+        #[arg(long, default_value_t = 10_000)]
+        batch_size: usize,
+    },
+    /// Print the columns of a fixed-width file, as given or detected
+    GetFixedWidthSchema {
+        file: String,
+        #[command(flatten)]
+        columns: FixedWidthOptions,
+        #[command(flatten)]
+        csv: CsvOptions,
+    },
+    /// Load a fixed-width file into a SQLite or PostgreSQL table
+    ImportFixedWidth {
+        #[arg(long)]
+        file: String,
+        /// Path to a SQLite database to load into
+        #[arg(
+            long,
+            required_unless_present = "connection",
+            conflicts_with = "connection"
+        )]
+        db: Option<String>,
+        /// PostgreSQL connection string to load into
+        #[arg(long)]
+        connection: Option<String>,
+        #[arg(long)]
+        table: String,
+        #[command(flatten)]
+        columns: FixedWidthOptions,
+        #[command(flatten)]
+        csv: CsvOptions,
+        #[command(flatten)]
+        constraints: ConstraintOptions,
+        #[arg(long, value_enum, default_value_t = Mode::Append)]
+        mode: Mode,
+        #[arg(long, default_value_t = 10_000)]
+        batch_size: usize,
+    },
+    /// Run the migration described by a TOML or JSON job spec file
+    RunSpec { spec: String },
+    /// Check a job spec file without running it
+    ValidateSpec { spec: String },
+    /// Continue an interrupted migration from its last checkpoint
+    Resume { job_id: String },
+    /// List interrupted migrations that can be resumed
+    Checkpoints,
+    /// Run a query against a SQLite database and print the result as TSV
+    ExecuteSqliteQuery {
+        #[arg(long)]
+        db: String,
+        query: String,
+    },
+    /// Run a query against a PostgreSQL database and print the result as TSV
+    ExecutePostgresQuery {
+        #[arg(long)]
+        connection: String,
+        query: String,
+    },
+}
+
+/// What to do when a destination table already exists.
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Mode {
+    /// Fail if the table already exists
+    Create,
+    /// Add rows to the table
+    Append,
+    /// Delete the table's rows first
+    Truncate,
+    /// Drop the table and create it again
+    Replace,
+}
+
+impl From<Mode> for LoadMode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Create => LoadMode::Create,
+            Mode::Append => LoadMode::Append,
+            Mode::Truncate => LoadMode::Truncate,
+            Mode::Replace => LoadMode::Replace,
+        }
+    }
+}
+
+/// How the rows column types are inferred from are picked.
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum SampleMethod {
+    /// The first rows of the file
+    First,
+    /// Rows picked at random from the whole file
+    Reservoir,
+    /// Every row of the file
+    Full,
+}
+
+/// What to do with a row whose number of fields differs from the header's.
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Ragged {
+    /// Fail the job
+    Error,
+    /// Fill in missing fields with NULL
+    Pad,
+    /// Drop extra fields
+    Truncate,
+    /// Pad short rows and truncate long ones
+    PadAndTruncate,
+    /// Leave the row out and write it to --reject-file
+    Reject,
+}
+
+impl From<Ragged> for RaggedRows {
+    fn from(ragged: Ragged) -> Self {
+        match ragged {
+            Ragged::Error => RaggedRows::Error,
+            Ragged::Pad => RaggedRows::Pad,
+            Ragged::Truncate => RaggedRows::Truncate,
+            Ragged::PadAndTruncate => RaggedRows::PadAndTruncate,
+            Ragged::Reject => RaggedRows::Reject,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(DatapumpError::Cancelled) => ExitCode::from(EXIT_CANCELLED),
+        Err(e) => {
+            eprintln!("error[{}]: {}", e.code(), e);
+            ExitCode::from(EXIT_FAILURE)
+        }
+    }
+}
+
+async fn run(command: Command) -> Result<(), DatapumpError> {
+    match command {
+        Command::GetCsvSchema { file, csv, sample } => {
+            let dialect = csv.dialect(&file)?;
+            let schema = csv_schema::get_csv_schema(&file, dialect, csv.policy(), sample.sample())?;
+            eprintln!("encoding: {}", schema.encoding);
+            if schema.compression != Compression::None {
+                eprintln!("compression: {:?}", schema.compression);
+            }
+            if let Some(dialect) = &schema.schema.dialect {
+                eprintln!(
+                    "delimiter: {:?}, quote: {:?}, escape: {:?}, terminator: {:?}, header: {}",
+                    dialect.delimiter,
+                    dialect.quote,
+                    dialect.escape,
+                    dialect.terminator,
+                    dialect.has_header
+                );
+                if dialect.skip_lines > 0 || dialect.skip_footer > 0 {
+                    eprintln!(
+                        "skipped: {} leading lines, {} footer lines",
+                        dialect.skip_lines, dialect.skip_footer
+                    );
+                }
+            }
+            eprintln!("sampled: {} rows ({})", schema.rows_sampled, schema.sample);
+            for column in &schema.schema.columns {
+                match &column.original {
+                    Some(original) if *original != column.name => {
+                        eprintln!("column: {:?} -> {}", original, column.name)
+                    }
+                    _ => {}
+                }
+            }
+            print_suggested_constraints(&schema.constraints);
+            let json = serde_json::to_string_pretty(&schema.schema)
+                .map_err(|e| DatapumpError::invalid(e.to_string()))?;
+            println!("{}", json);
+            Ok(())
+        }
+        Command::CsvToSqlite {
+            files,
+            source_file_column,
+            db,
+            table,
+            schema,
+            csv,
+            sample,
+            ragged,
+            constraints,
+            batch_size,
+        } => {
+            let mut spec = JobSpec::new(
+                csv.files_source(files, source_file_column),
+                SinkConfig::Sqlite { path: db },
+                table,
+            );
+            spec.schema = schema;
+            spec.batch_size = batch_size;
+            spec.sample = sample.sample();
+            spec.ragged = ragged.policy();
+            spec.constraints = constraints.constraints();
+            run_migration(&spec).await
+        }
+        Command::CsvToPostgres {
+            files,
+            source_file_column,
+            connection,
+            table,
+            schema,
+            csv,
+            sample,
+            ragged,
+            constraints,
+            batch_size,
+        } => {
+            let mut spec = JobSpec::new(
+                csv.files_source(files, source_file_column),
+                SinkConfig::Postgres {
+                    connection_string: connection,
+                },
+                table,
+            );
+            spec.schema = schema;
+            spec.batch_size = batch_size;
+            spec.sample = sample.sample();
+            spec.ragged = ragged.policy();
+            spec.constraints = constraints.constraints();
+            run_migration(&spec).await
+        }
+        Command::ImportCsvToPostgres {
+            file,
+            connection,
+            table,
+            csv,
+            ragged,
+            schema,
+            sample,
+        } => {
+            let (dialect, schema) = match schema {
+                // A schema that records its dialect is read in it, not sniffed again
+                Some(schema) => match &schema.dialect {
+                    Some(dialect) => (dialect.clone(), schema),
+                    None => (csv.dialect(&file)?, schema),
+                },
+                None => {
+                    let dialect = csv.dialect(&file)?;
+                    let schema = csv_schema::get_csv_schema(
+                        &file,
+                        dialect.clone(),
+                        csv.policy(),
+                        sample.sample(),
+                    )?
+                    .schema;
+                    (dialect, schema)
+                }
+            };
+            let fields = schema
+                .column_definitions()
+                .into_iter()
+                .map(|(name, typ)| serde_json::json!({ "name": name, "type": typ }))
+                .collect();
+
+            let job_id = watch_for_ctrl_c(uuid::Uuid::new_v4().to_string());
+            report_resumable(
+                &job_id,
+                postgres_copy::import_csv(
+                    &StderrProgress,
+                    &connection,
+                    &file,
+                    &table,
+                    &dialect,
+                    fields,
+                    &ragged.policy(),
+                    Some(job_id.clone()),
+                )
+                .await,
+            )
+        }
+        Command::ListZip { file, csv } => {
+            for entry in archive::list_entries(&file, &csv.options(), csv.policy())? {
+                print!("{}\t{}\t{} bytes", entry.name, entry.table_name, entry.size);
+                match &entry.schema {
+                    Some(schema) => {
+                        println!();
+                        // One column per line, as names may hold ',' or ':'
+                        for (name, typ) in schema.schema.column_definitions() {
+                            println!("\t{}\t{}", name, typ);
+                        }
+                    }
+                    None => println!("\tskipped: {}", entry.error.as_deref().unwrap_or_default()),
+                }
+            }
+            Ok(())
+        }
+        Command::ImportZip {
+            file,
+            members,
+            db,
+            connection,
+            csv,
+            ragged,
+            mode,
+            batch_size,
+        } => {
+            let destination = match (db, connection) {
+                (Some(path), _) => SinkConfig::Sqlite { path },
+                (None, Some(connection_string)) => SinkConfig::Postgres { connection_string },
+                (None, None) => unreachable!("clap requires --db or --connection"),
+            };
+            let imported = migrate::import_archive(
+                &StderrProgress,
+                &csv.source(file),
+                &destination,
+                &members,
+                mode.into(),
+                batch_size,
+                &ragged.policy(),
+            )
+            .await?;
+            for member in imported {
+                println!(
+                    "{}\t{}\t{} rows",
+                    member.member, member.table_name, member.rows
+                );
+            }
+            Ok(())
+        }
+        Command::GetFixedWidthSchema { file, columns, csv } => {
+            let layout =
+                fixed_width::layout(&file, &columns.columns()?, &csv.options(), csv.policy())?;
+            eprintln!("encoding: {}", layout.dialect.encoding);
+            eprintln!("header: {}", layout.dialect.has_header);
+            for column in &layout.columns {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    column.name,
+                    column.start,
+                    column.length,
+                    column.column_type.as_deref().unwrap_or("text")
+                );
+            }
+            Ok(())
+        }
+        Command::ImportFixedWidth {
+            file,
+            db,
+            connection,
+            table,
+            columns,
+            csv,
+            constraints,
+            mode,
+            batch_size,
+        } => {
+            let destination = match (db, connection) {
+                (Some(path), _) => SinkConfig::Sqlite { path },
+                (None, Some(connection_string)) => SinkConfig::Postgres { connection_string },
+                (None, None) => unreachable!("clap requires --db or --connection"),
+            };
+            let mut spec = JobSpec::new(columns.source(file, &csv)?, destination, table);
+            spec.mode = mode.into();
+            spec.batch_size = batch_size;
+            spec.constraints = constraints.constraints();
+            run_migration(&spec).await
+        }
+        Command::RunSpec { spec } => run_migration(&JobSpec::load(&spec)?).await,
+        Command::ValidateSpec { spec } => {
+            let spec = JobSpec::load(&spec)?;
+            spec.validate()?;
+            let columns = spec.resolve_columns()?;
+            spec.constraints.validate(&columns)?;
+            for (name, typ) in columns {
+                println!("{}\t{}", name, typ);
+            }
+            Ok(())
+        }
+        Command::Resume { job_id } => {
+            let job_id = watch_for_ctrl_c(job_id);
+            report_resumable(
+                &job_id,
+                migrate::resume_migration(&StderrProgress, &job_id).await,
+            )
+        }
+        Command::Checkpoints => {
+            for checkpoint in checkpoint::list() {
+                println!(
+                    "{}\t{}\t{} rows committed",
+                    checkpoint.job_id, checkpoint.spec.table_name, checkpoint.rows_committed
+                );
+            }
+            Ok(())
+        }
+        Command::ExecuteSqliteQuery { db, query } => {
+            print_result(query::execute_sqlite_query(&db, &query)?);
+            Ok(())
+        }
+        Command::ExecutePostgresQuery { connection, query } => {
+            print_result(query::execute_postgres_query(&connection, &query).await?);
+            Ok(())
+        }
+    }
+}
+
+/// Print the constraints the sample suggests, as lists `--not-null` and
+/// `--primary-key` take.
+fn print_suggested_constraints(constraints: &SuggestedConstraints) {
+    if !constraints.not_null.is_empty() {
+        eprintln!("not null: {}", constraints.not_null.join(","));
+    }
+    for key in &constraints.primary_keys {
+        let estimated = if key.exact { "" } else { " (estimated)" };
+        eprintln!(
+            "primary key candidate: {}{}",
+            key.columns.join(","),
+            estimated
+        );
+    }
+}
+
+/// A `--schema` value: a file holding the schema as JSON, or the JSON
+/// itself.
+fn parse_schema_arg(value: &str) -> Result<Schema, DatapumpError> {
+    if std::path::Path::new(value).is_file() {
+        let text = std::fs::read_to_string(value).context(&format!("Failed to read {}", value))?;
+        Schema::parse(&text)
+    } else {
+        Schema::parse(value)
+    }
+}
+
+async fn run_migration(spec: &JobSpec) -> Result<(), DatapumpError> {
+    let job_id = watch_for_ctrl_c(uuid::Uuid::new_v4().to_string());
+    report_resumable(
+        &job_id,
+        migrate::run_migration(&StderrProgress, spec, Some(job_id.clone())).await,
+    )
+}
+
+/// Tell the user how to continue a failed migration that left a checkpoint.
+fn report_resumable<T>(
+    job_id: &str,
+    result: Result<T, DatapumpError>,
+) -> Result<(), DatapumpError> {
+    if result.as_ref().is_err_and(|e| !e.is_cancelled()) && Checkpoint::load(job_id).is_ok() {
+        eprintln!(
+            "To continue from the last checkpoint: datapump resume {}",
+            job_id
+        );
+    }
+    result.map(|_| ())
+}
+
+/// Cancel the job with the given id when Ctrl-C is pressed.
+fn watch_for_ctrl_c(job_id: String) -> String {
+    let id = job_id.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("Cancelling...");
+            if let Some(job) = jobs::registry().get(&id) {
+                job.cancel();
+            }
+        }
+    });
+    job_id
+}
+
+fn print_result(result: QueryResult) {
+    println!("{}", result.columns.join("\t"));
+    for row in result.rows {
+        println!("{}", row.join("\t"));
+    }
+}
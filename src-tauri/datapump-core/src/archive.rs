@@ -0,0 +1,290 @@
+// src/archive.rs
+
+use crate::csv_schema::{self, CsvSchema, Sample};
+use crate::dialect::{Dialect, DialectOptions};
+use crate::error::{Context, DatapumpError};
+use crate::headers::{self, HeaderPolicy};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Take};
+use std::path::Path;
+use zip::{CompressionMethod, ZipArchive};
+
+/// Separates an archive's path from a member's name, as in
+/// "extract.zip!/orders.csv". Such a path can be used wherever a file path
+/// is expected.
+pub const MEMBER_SEPARATOR: &str = "!/";
+
+/// Extensions of members that can be imported as delimited text.
+const DELIMITED_EXTENSIONS: [&str; 6] = ["csv", "tsv", "txt", "tab", "psv", "dat"];
+
+/// Extensions of compressed files, left out of table names.
+const COMPRESSED_EXTENSIONS: [&str; 5] = ["gz", "zst", "bz2", "xz", "zip"];
+
+/// A file inside a zip archive, as listed for the user to choose from.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveEntry {
+    /// Name as stored in the archive, including any folders.
+    pub name: String,
+    /// Path of the member, to be used as a source path.
+    pub path: String,
+    /// Uncompressed size in bytes.
+    pub size: u64,
+    pub compressed_size: u64,
+    /// Table the member is imported into, unique within the archive.
+    pub table_name: String,
+    /// Schema sniffed from the member, if it is in a supported format.
+    pub schema: Option<CsvSchema>,
+    /// Why the member cannot be imported, if it cannot.
+    pub error: Option<String>,
+}
+
+impl ArchiveEntry {
+    pub fn is_importable(&self) -> bool {
+        self.schema.is_some()
+    }
+}
+
+/// The path of `member` inside the zip archive at `archive`.
+pub fn member_path(archive: &str, member: &str) -> String {
+    format!("{}{}{}", archive, MEMBER_SEPARATOR, member)
+}
+
+/// Whether the file at `file_path` is a zip archive.
+pub fn is_zip(file_path: &str) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(file_path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|_| magic == *b"PK\x03\x04" || magic == *b"PK\x05\x06")
+}
+
+/// A member of a zip archive, found in the archive's central directory,
+/// which is read once.
+pub(crate) struct Member {
+    archive: String,
+    name: String,
+    zip: ZipArchive<File>,
+    index: usize,
+}
+
+/// The member `path` refers to: either "archive.zip!/member", or the one
+/// data file in a zip archive. `None` for other files.
+pub(crate) fn locate(path: &str) -> Result<Option<Member>, DatapumpError> {
+    if Path::new(path).is_file() {
+        if !is_zip(path) {
+            return Ok(None);
+        }
+        let mut zip = open(path)?;
+        let names = data_members(&mut zip);
+        return match names.as_slice() {
+            [name] => Member::find(path, name, zip).map(Some),
+            [] => Err(DatapumpError::invalid(format!(
+                "{} is an empty zip archive",
+                path
+            ))),
+            [first, ..] => Err(DatapumpError::invalid(format!(
+                "{} is a zip archive of {} files; choose one, e.g. {}",
+                path,
+                names.len(),
+                member_path(path, first)
+            ))),
+        };
+    }
+
+    // The archive is the longest prefix that is a file, as folder names
+    // inside it may contain the separator too
+    let split = path
+        .match_indices(MEMBER_SEPARATOR)
+        .map(|(i, _)| (&path[..i], &path[i + MEMBER_SEPARATOR.len()..]))
+        .find(|(archive, _)| Path::new(archive).is_file());
+    split
+        .map(|(archive, member)| Member::find(archive, member, open(archive)?))
+        .transpose()
+}
+
+fn open(archive: &str) -> Result<ZipArchive<File>, DatapumpError> {
+    let file = File::open(archive).context("Failed to open archive")?;
+    ZipArchive::new(file).map_err(|e| {
+        DatapumpError::invalid(format!("{} is not a valid zip archive: {}", archive, e))
+    })
+}
+
+/// Names of the members of the zip archive at `archive` that hold data.
+pub fn members(archive: &str) -> Result<Vec<String>, DatapumpError> {
+    Ok(data_members(&mut open(archive)?))
+}
+
+/// Names of the members that hold data, leaving out folders and the
+/// metadata macOS adds to archives it creates.
+fn data_members(archive: &mut ZipArchive<File>) -> Vec<String> {
+    archive
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .filter(|name| !name.starts_with("__MACOSX/"))
+        .filter(|name| {
+            let file_name = name.rsplit('/').next().unwrap_or(name);
+            !file_name.starts_with("._") && file_name != ".DS_Store"
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// How a member's data is compressed inside the archive.
+pub(crate) enum Method {
+    Stored,
+    Deflated,
+    Bzip2,
+    Zstd,
+    Xz,
+}
+
+impl Member {
+    fn find(archive: &str, name: &str, zip: ZipArchive<File>) -> Result<Self, DatapumpError> {
+        let index = index_of(&zip, archive, name)?;
+        Ok(Member {
+            archive: archive.to_string(),
+            name: name.to_string(),
+            zip,
+            index,
+        })
+    }
+
+    /// The still compressed data of the member, read straight from the
+    /// archive file, and how to decompress it. Nothing is extracted to disk.
+    pub(crate) fn data(mut self) -> Result<(Take<File>, Method), DatapumpError> {
+        let (archive, member) = (&self.archive, &self.name);
+        let entry = self.zip.by_index_raw(self.index).map_err(|e| {
+            DatapumpError::invalid(format!("Cannot read {} in {}: {}", member, archive, e))
+        })?;
+        if entry.encrypted() {
+            return Err(DatapumpError::invalid(format!(
+                "{} in {} is encrypted",
+                member, archive
+            )));
+        }
+
+        let method = match entry.compression() {
+            m if m == CompressionMethod::STORE => Method::Stored,
+            m if m == CompressionMethod::DEFLATE => Method::Deflated,
+            m if m == CompressionMethod::BZIP2 => Method::Bzip2,
+            m if m == CompressionMethod::ZSTD => Method::Zstd,
+            m if m == CompressionMethod::XZ => Method::Xz,
+            m => {
+                return Err(DatapumpError::invalid(format!(
+                    "{} in {} uses an unsupported compression method ({})",
+                    member, archive, m
+                )))
+            }
+        };
+        let (start, length) = (entry.data_start(), entry.compressed_size());
+        drop(entry);
+
+        let mut file = self.zip.into_inner();
+        file.seek(SeekFrom::Start(start))
+            .context("Failed to read archive")?;
+        Ok((file.take(length), method))
+    }
+
+    /// Compressed size of the member, which is what reading it is measured in.
+    pub(crate) fn size(&mut self) -> Option<u64> {
+        let size = self.zip.by_index_raw(self.index).ok()?.compressed_size();
+        Some(size)
+    }
+}
+
+/// Where `member` is in the central directory of `zip`, the archive at `archive`.
+fn index_of(zip: &ZipArchive<File>, archive: &str, member: &str) -> Result<usize, DatapumpError> {
+    zip.index_for_name(member)
+        .ok_or_else(|| DatapumpError::invalid(format!("No file named {} in {}", member, archive)))
+}
+
+/// List the members of the zip archive at `archive` with the table each
+/// would be imported into and, for members in a supported format, their
+/// sniffed schema. Members are sniffed as they are read, not extracted.
+pub fn list_entries(
+    archive: &str,
+    options: &DialectOptions,
+    policy: HeaderPolicy,
+) -> Result<Vec<ArchiveEntry>, DatapumpError> {
+    let mut zip = open(archive)?;
+    let names = data_members(&mut zip);
+    let tables = table_names(&names);
+
+    let mut entries = Vec::with_capacity(names.len());
+    for (name, table_name) in names.into_iter().zip(tables) {
+        let (size, compressed_size) = {
+            let index = index_of(&zip, archive, &name)?;
+            let entry = zip
+                .by_index_raw(index)
+                .map_err(|e| DatapumpError::invalid(format!("Cannot read {}: {}", name, e)))?;
+            (entry.size(), entry.compressed_size())
+        };
+        let path = member_path(archive, &name);
+
+        let (schema, error) = if is_delimited(&name) {
+            match Dialect::sniff(&path, options).and_then(|dialect| {
+                csv_schema::get_csv_schema(&path, dialect, policy, Sample::default())
+            }) {
+                Ok(schema) => (Some(schema), None),
+                Err(e) => (None, Some(e.to_string())),
+            }
+        } else {
+            (None, Some("Unsupported file format".to_string()))
+        };
+
+        entries.push(ArchiveEntry {
+            name,
+            path,
+            size,
+            compressed_size,
+            table_name,
+            schema,
+            error,
+        });
+    }
+    Ok(entries)
+}
+
+fn extension(name: &str) -> Option<String> {
+    let (stem, ext) = name.rsplit_once('.')?;
+    (!stem.is_empty()).then(|| ext.to_lowercase())
+}
+
+/// Whether a member is delimited text, compressed or not.
+pub fn is_delimited(name: &str) -> bool {
+    let mut name = name;
+    while let Some(ext) = extension(name) {
+        if DELIMITED_EXTENSIONS.contains(&ext.as_str()) {
+            return true;
+        }
+        if !COMPRESSED_EXTENSIONS.contains(&ext.as_str()) {
+            return false;
+        }
+        name = &name[..name.len() - ext.len() - 1];
+    }
+    false
+}
+
+/// A table name for each member: its file name without folders or known
+/// extensions, made safe and unique the way column names are.
+pub fn table_names(members: &[String]) -> Vec<String> {
+    let stems: Vec<&str> = members
+        .iter()
+        .map(|name| {
+            let mut stem = name.rsplit('/').next().unwrap_or(name);
+            while let Some(ext) = extension(stem) {
+                if !DELIMITED_EXTENSIONS.contains(&ext.as_str())
+                    && !COMPRESSED_EXTENSIONS.contains(&ext.as_str())
+                {
+                    break;
+                }
+                stem = &stem[..stem.len() - ext.len() - 1];
+            }
+            stem
+        })
+        .collect();
+    headers::normalize(stems, HeaderPolicy { snake_case: true })
+        .into_iter()
+        .map(|column| column.name)
+        .collect()
+}
@@ -0,0 +1,206 @@
+// src/cardinality.rs
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+
+/// Distinct values counted exactly before switching to a sketch. Beyond it,
+/// holding every value's hash would take more memory than the answer is
+/// worth.
+pub const EXACT_LIMIT: usize = 100_000;
+
+/// Bits of the hash that pick a sketch register: 2^14 registers of a byte
+/// each, for a standard error of about 0.8%.
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+
+/// The hash a value is counted by. Not randomly keyed, so the same values
+/// always give the same count.
+pub fn hash(value: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The hash of a pair of values, from the hash of each. Mixed like the
+/// SplitMix64 output, so that the pair (a, b) differs from (b, a).
+pub fn combine(a: u64, b: u64) -> u64 {
+    let mut z = a.rotate_left(32) ^ b.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Hashes values are counted by are already well mixed, so sets of them
+/// use them as they are rather than hashing them again.
+#[derive(Default)]
+pub struct PassThrough(u64);
+
+impl Hasher for PassThrough {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = n;
+    }
+}
+
+type Hashes = HashSet<u64, BuildHasherDefault<PassThrough>>;
+
+/// The number of distinct values seen: exact, by their hashes, up to
+/// `EXACT_LIMIT`, then estimated with a HyperLogLog sketch of fixed size.
+#[derive(Debug, Clone)]
+pub enum Cardinality {
+    Exact(Hashes),
+    Sketch(Box<[u8; REGISTERS]>),
+}
+
+impl Default for Cardinality {
+    fn default() -> Self {
+        Cardinality::Exact(Hashes::default())
+    }
+}
+
+impl Cardinality {
+    /// Count a value by its `hash`. Returns `false` if it was certainly seen
+    /// before, which a sketch can never tell.
+    pub fn insert(&mut self, hash: u64) -> bool {
+        match self {
+            Cardinality::Exact(hashes) => {
+                let new = hashes.insert(hash);
+                if hashes.len() > EXACT_LIMIT {
+                    let mut registers = Box::new([0u8; REGISTERS]);
+                    hashes
+                        .iter()
+                        .for_each(|&hash| add_to_sketch(&mut registers, hash));
+                    *self = Cardinality::Sketch(registers);
+                }
+                new
+            }
+            Cardinality::Sketch(registers) => {
+                add_to_sketch(registers, hash);
+                true
+            }
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Cardinality::Exact(_))
+    }
+
+    pub fn count(&self) -> u64 {
+        match self {
+            Cardinality::Exact(hashes) => hashes.len() as u64,
+            Cardinality::Sketch(registers) => estimate(registers),
+        }
+    }
+
+    /// Whether `count` distinct values were seen, all different. A sketch
+    /// can only say its estimate is within its error of `count`.
+    pub fn all_distinct(&self, count: u64) -> bool {
+        match self {
+            Cardinality::Exact(hashes) => hashes.len() as u64 == count,
+            Cardinality::Sketch(registers) => estimate(registers) as f64 >= count as f64 * 0.98,
+        }
+    }
+}
+
+/// The register is picked by the top bits of the hash and keeps the
+/// longest run of leading zeros seen in the rest.
+fn add_to_sketch(registers: &mut [u8; REGISTERS], hash: u64) {
+    let index = (hash >> (64 - PRECISION)) as usize;
+    let rest = (hash << PRECISION) | (1 << (PRECISION - 1));
+    let rank = rest.leading_zeros() as u8 + 1;
+    registers[index] = registers[index].max(rank);
+}
+
+fn estimate(registers: &[u8; REGISTERS]) -> u64 {
+    let m = REGISTERS as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let sum: f64 = registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+    let raw = alpha * m * m / sum;
+    let zeros = registers.iter().filter(|&&r| r == 0).count();
+    // Few values leave registers empty; counting those is more accurate then
+    if raw <= 2.5 * m && zeros > 0 {
+        (m * (m / zeros as f64).ln()).round() as u64
+    } else {
+        raw.round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(values: impl Iterator<Item = u64>) -> Cardinality {
+        let mut cardinality = Cardinality::default();
+        for value in values {
+            cardinality.insert(hash(&value.to_string()));
+        }
+        cardinality
+    }
+
+    fn assert_close(estimate: u64, actual: u64) {
+        let error = (estimate as f64 - actual as f64).abs() / actual as f64;
+        assert!(error < 0.03, "estimated {} for {}", estimate, actual);
+    }
+
+    #[test]
+    fn exact_up_to_the_limit() {
+        let mut cardinality = count(0..1000);
+        assert!(cardinality.is_exact());
+        assert_eq!(cardinality.count(), 1000);
+        assert!(cardinality.all_distinct(1000));
+        assert!(!cardinality.insert(hash("7")));
+        assert!(cardinality.insert(hash("1000")));
+        assert!(!cardinality.all_distinct(1002));
+    }
+
+    #[test]
+    fn sketch_past_the_limit() {
+        let cardinality = count(0..EXACT_LIMIT as u64 + 1);
+        assert!(!cardinality.is_exact());
+        assert_close(cardinality.count(), EXACT_LIMIT as u64 + 1);
+    }
+
+    #[test]
+    fn sketch_estimate_is_close() {
+        for actual in [150_000, 500_000, 1_000_000] {
+            let cardinality = count(0..actual);
+            assert_close(cardinality.count(), actual);
+            assert!(cardinality.all_distinct(actual));
+        }
+    }
+
+    #[test]
+    fn sketch_ignores_repeats() {
+        let cardinality = count((0..400_000).map(|n| n % 200_000));
+        assert_close(cardinality.count(), 200_000);
+        assert!(!cardinality.all_distinct(400_000));
+    }
+
+    #[test]
+    fn sketch_of_few_values_counts_empty_registers() {
+        let mut registers = Box::new([0u8; REGISTERS]);
+        for n in 0..1000 {
+            add_to_sketch(&mut registers, hash(&n.to_string()));
+        }
+        assert_close(estimate(&registers), 1000);
+        assert_eq!(estimate(&Box::new([0u8; REGISTERS])), 0);
+    }
+
+    #[test]
+    fn combined_hashes_depend_on_order() {
+        let (a, b) = (hash("a"), hash("b"));
+        assert_ne!(combine(a, b), combine(b, a));
+        assert_eq!(combine(a, b), combine(a, b));
+        assert_eq!(hash("a"), a);
+    }
+}
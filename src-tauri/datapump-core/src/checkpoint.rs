@@ -0,0 +1,142 @@
+// src/checkpoint.rs
+
+use crate::error::{Context, DatapumpError};
+use crate::ragged::RaggedRowCounts;
+use crate::spec::JobSpec;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Table each destination keeps its committed checkpoints in, written in the
+/// same transaction as the rows, so it is never out of step with them.
+pub const CHECKPOINT_TABLE: &str = "_datapump_checkpoints";
+
+static STATE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Store checkpoints under `dir` instead of the default location. Must be
+/// called before the first migration starts; later calls are ignored.
+pub fn set_state_dir(dir: impl Into<PathBuf>) {
+    let _ = STATE_DIR.set(dir.into());
+}
+
+/// Where checkpoints live: the directory given to `set_state_dir`, else
+/// `$DATAPUMP_STATE_DIR`, else `~/.datapump/checkpoints`.
+pub fn state_dir() -> &'static Path {
+    STATE_DIR.get_or_init(|| {
+        if let Some(dir) = std::env::var_os("DATAPUMP_STATE_DIR") {
+            return PathBuf::from(dir);
+        }
+        std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join(".datapump")
+            .join("checkpoints")
+    })
+}
+
+/// A point in the source that reading can restart from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourcePosition {
+    /// Byte offset of the next unread row.
+    pub byte: u64,
+    /// Line the next unread row starts on.
+    pub line: u64,
+    /// Number of records (including the header) read before it.
+    pub record: u64,
+    /// Which file the row is in, for a source of several files. `byte` then
+    /// counts the files before it too, while `line` and `record` are within it.
+    #[serde(default)]
+    pub file: usize,
+    /// Ragged rows fitted or rejected before it, so a resumed job reports
+    /// them all.
+    #[serde(default, skip_serializing_if = "RaggedRowCounts::is_empty")]
+    pub ragged: RaggedRowCounts,
+}
+
+/// How far a migration got, saved after every batch the destination committed.
+///
+/// The spec is stored with its columns already resolved, so a resumed job
+/// loads with exactly the schema the interrupted one used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub job_id: String,
+    pub spec: JobSpec,
+    /// Where the first row that is not yet committed starts; `None` until the
+    /// first batch has been committed.
+    pub position: Option<SourcePosition>,
+    pub rows_committed: usize,
+    /// Seconds since the Unix epoch.
+    pub updated_at: u64,
+}
+
+impl Checkpoint {
+    /// A checkpoint for a job that has not committed anything yet.
+    pub fn new(job_id: &str, spec: JobSpec) -> Self {
+        Checkpoint {
+            job_id: job_id.to_string(),
+            spec,
+            position: None,
+            rows_committed: 0,
+            updated_at: now(),
+        }
+    }
+
+    fn path(job_id: &str) -> PathBuf {
+        state_dir().join(format!("{}.json", job_id))
+    }
+
+    /// Load the checkpoint of an interrupted job.
+    pub fn load(job_id: &str) -> Result<Self, DatapumpError> {
+        let path = Self::path(job_id);
+        if !path.exists() {
+            return Err(DatapumpError::invalid(format!(
+                "No checkpoint for job {}",
+                job_id
+            )));
+        }
+        let text = std::fs::read_to_string(&path).context("Failed to read checkpoint")?;
+        serde_json::from_str(&text)
+            .map_err(|e| DatapumpError::invalid(format!("Invalid checkpoint {}: {}", job_id, e)))
+    }
+
+    /// Write the checkpoint atomically, so a crash mid-write keeps the old one.
+    pub fn save(&mut self) -> Result<(), DatapumpError> {
+        self.updated_at = now();
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| DatapumpError::invalid(e.to_string()))?;
+
+        let path = Self::path(&self.job_id);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::create_dir_all(state_dir()).context("Failed to create checkpoint directory")?;
+        std::fs::write(&tmp, text).context("Failed to write checkpoint")?;
+        std::fs::rename(&tmp, &path).context("Failed to write checkpoint")
+    }
+
+    /// Forget a job's checkpoint once it has completed or been cancelled.
+    pub fn remove(job_id: &str) {
+        let _ = std::fs::remove_file(Self::path(job_id));
+    }
+}
+
+/// Every checkpoint on disk, i.e. every job that can be resumed.
+pub fn list() -> Vec<Checkpoint> {
+    let Ok(entries) = std::fs::read_dir(state_dir()) else {
+        return Vec::new();
+    };
+    let mut checkpoints: Vec<Checkpoint> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .filter_map(|text| serde_json::from_str(&text).ok())
+        .collect();
+    checkpoints.sort_by_key(|checkpoint| std::cmp::Reverse(checkpoint.updated_at));
+    checkpoints
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
@@ -0,0 +1,172 @@
+// src/compression.rs
+
+use crate::archive::{self, Method};
+use crate::error::{Context, DatapumpError};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// How a file is compressed, told by its first bytes rather than its name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+    /// A member of a zip archive, see `archive`.
+    Zip,
+}
+impl Compression {
+    /// Recognize a compressed stream by its magic bytes.
+    pub fn from_magic(start: &[u8]) -> Compression {
+        if start.starts_with(&[0x1F, 0x8B]) {
+            Compression::Gzip
+        } else if start.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Compression::Zstd
+        } else if start.starts_with(b"BZh")
+            && start.get(3).is_some_and(|b| (b'1'..=b'9').contains(b))
+        {
+            Compression::Bzip2
+        } else if start.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// Detect how `file_path` is compressed.
+pub fn detect(file_path: &str) -> Result<Compression, DatapumpError> {
+    if archive::locate(file_path)?.is_some() {
+        return Ok(Compression::Zip);
+    }
+    let mut file = File::open(file_path).context("Failed to open file")?;
+    let mut start = Vec::with_capacity(6);
+    file.by_ref()
+        .take(6)
+        .read_to_end(&mut start)
+        .context("Failed to read file")?;
+    Ok(Compression::from_magic(&start))
+}
+
+/// Size of the input at `file_path` as reading it is measured: the file on
+/// disk, or for a member of a zip archive its compressed size.
+pub fn input_size(file_path: &str) -> Option<u64> {
+    match archive::locate(file_path).ok()? {
+        Some(mut member) => member.size(),
+        None => std::fs::metadata(file_path).map(|m| m.len()).ok(),
+    }
+}
+
+/// A file decompressed as it is read. Only the compressed bytes pulled from
+/// disk are counted, which is what progress is measured against.
+pub struct Decompressed {
+    compression: Compression,
+    reader: Box<dyn Read + Send>,
+    consumed: Arc<AtomicU64>,
+}
+
+impl Decompressed {
+    /// Open `file_path`, decompressing it if its magic bytes say it is
+    /// compressed. Multi-member gzip and multi-frame zstd files are read
+    /// to the end, as the command-line tools do. A member of a zip archive
+    /// is read straight out of the archive.
+    pub fn open(file_path: &str) -> Result<Self, DatapumpError> {
+        let consumed = Arc::new(AtomicU64::new(0));
+
+        let (compression, reader) = match archive::locate(file_path)? {
+            Some(member) => {
+                let (data, method) = member.data()?;
+                let data = BufReader::new(CountingReader {
+                    inner: data,
+                    consumed: Arc::clone(&consumed),
+                });
+                let member: Box<dyn Read + Send> = match method {
+                    Method::Stored => Box::new(data),
+                    Method::Deflated => Box::new(flate2::bufread::DeflateDecoder::new(data)),
+                    Method::Bzip2 => Box::new(bzip2::bufread::BzDecoder::new(data)),
+                    Method::Zstd => Box::new(
+                        zstd::stream::read::Decoder::with_buffer(data)
+                            .context("Failed to start zstd decompression")?,
+                    ),
+                    Method::Xz => Box::new(liblzma::bufread::XzDecoder::new(data)),
+                };
+                // The member may be compressed itself, as in "orders.csv.gz"
+                let mut member = BufReader::new(member);
+                let start = member.fill_buf().context("Failed to read archive")?;
+                let nested = Compression::from_magic(start);
+                (Compression::Zip, decompress(nested, member)?)
+            }
+            None => {
+                let compression = detect(file_path)?;
+                let file = BufReader::new(CountingReader {
+                    inner: File::open(file_path).context("Failed to open file")?,
+                    consumed: Arc::clone(&consumed),
+                });
+                (compression, decompress(compression, file)?)
+            }
+        };
+
+        Ok(Decompressed {
+            compression,
+            reader,
+            consumed,
+        })
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Bytes of the file on disk consumed so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.consumed.load(Ordering::Relaxed)
+    }
+
+    /// A shared count of `bytes_read`, for when the reader is handed on.
+    pub fn counter(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.consumed)
+    }
+}
+
+impl Read for Decompressed {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+/// Wrap `reader` in a streaming decoder for `compression`.
+fn decompress<R: BufRead + Send + 'static>(
+    compression: Compression,
+    reader: R,
+) -> Result<Box<dyn Read + Send>, DatapumpError> {
+    Ok(match compression {
+        Compression::None | Compression::Zip => Box::new(reader),
+        Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(
+            zstd::stream::read::Decoder::with_buffer(reader)
+                .context("Failed to start zstd decompression")?,
+        ),
+        Compression::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(reader)),
+        Compression::Xz => Box::new(liblzma::bufread::XzDecoder::new_multi_decoder(reader)),
+    })
+}
+
+/// Counts the bytes read from the file, for progress.
+struct CountingReader<R> {
+    inner: R,
+    consumed: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.consumed.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
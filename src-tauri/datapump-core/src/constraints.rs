@@ -0,0 +1,99 @@
+// src/constraints.rs
+
+use crate::error::DatapumpError;
+use crate::types::ColumnType;
+use serde::{Deserialize, Serialize};
+
+/// Constraints a table is created with, beyond the types of its columns.
+/// Tables that already exist are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Constraints {
+    /// Columns that may not be NULL.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub not_null: Vec<String>,
+    /// Columns of the primary key, which are never NULL either.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub primary_key: Vec<String>,
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        self.not_null.is_empty() && self.primary_key.is_empty()
+    }
+
+    /// Check that every constrained column is one of `columns`.
+    pub fn validate(&self, columns: &[(String, String)]) -> Result<(), DatapumpError> {
+        let unknown = self
+            .not_null
+            .iter()
+            .chain(&self.primary_key)
+            .find(|name| !columns.iter().any(|(column, _)| column == *name));
+        match unknown {
+            Some(name) => Err(DatapumpError::invalid(format!(
+                "Constraint on unknown column '{}'",
+                name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// What goes between the parentheses of CREATE TABLE: each column with
+    /// the type `sql_type` gives it and NOT NULL where constrained, then the
+    /// primary key.
+    pub(crate) fn table_definition(
+        &self,
+        columns: &[(String, String)],
+        sql_type: impl Fn(&ColumnType) -> String,
+    ) -> String {
+        let mut definitions: Vec<String> = columns
+            .iter()
+            .map(|(name, typ)| {
+                let mut definition = format!("\"{}\" {}", name, sql_type(&ColumnType::parse(typ)));
+                if self.not_null.contains(name) {
+                    definition.push_str(" NOT NULL");
+                }
+                definition
+            })
+            .collect();
+        if !self.primary_key.is_empty() {
+            let key: Vec<String> = self
+                .primary_key
+                .iter()
+                .map(|name| format!("\"{}\"", name))
+                .collect();
+            definitions.push(format!("PRIMARY KEY ({})", key.join(", ")));
+        }
+        definitions.join(", ")
+    }
+}
+
+/// How many of a column's sampled values were empty, and how many distinct.
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnStats {
+    pub name: String,
+    pub nulls: u64,
+    pub distinct: u64,
+    /// Whether `distinct` was counted exactly or estimated, for columns with
+    /// too many distinct values to keep track of each.
+    pub exact: bool,
+}
+
+/// A column, or pair of columns, whose sampled values never repeat and are
+/// never empty.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyCandidate {
+    pub columns: Vec<String>,
+    /// Whether uniqueness was checked exactly or estimated.
+    pub exact: bool,
+}
+
+/// Constraints the sampled values suggest. Nothing is applied unless the
+/// user accepts it, since rows outside the sample may not agree.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SuggestedConstraints {
+    /// Columns without an empty value.
+    pub not_null: Vec<String>,
+    /// Single columns first, then pairs, in column order.
+    pub primary_keys: Vec<KeyCandidate>,
+}
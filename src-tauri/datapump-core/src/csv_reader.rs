@@ -0,0 +1,25 @@
+// src/csv_reader.rs
+
+use crate::dialect::Dialect;
+use crate::error::DatapumpError;
+use crate::input::CsvInput;
+
+/// Create a fresh `csv::Reader` starting from the beginning of the file
+/// for the given dialect, skipping the header row if it has one. The file is
+/// decoded from the dialect's encoding as it is read, and any preamble,
+/// comment or footer lines are left out. Unless `flexible`, a row with a
+/// different number of fields than the first is an error.
+pub fn create_csv_reader(
+    file_path: &str,
+    dialect: &Dialect,
+    flexible: bool,
+) -> Result<csv::Reader<CsvInput>, DatapumpError> {
+    let input = CsvInput::open(file_path, dialect)?;
+
+    let rdr = dialect
+        .reader_builder()
+        .flexible(flexible)
+        .from_reader(input);
+
+    Ok(rdr)
+}
@@ -0,0 +1,716 @@
+// src/csv_schema.rs
+
+use crate::cardinality::{self, Cardinality};
+use crate::compression::{self, Compression};
+use crate::constraints::{ColumnStats, KeyCandidate, SuggestedConstraints};
+use crate::dialect::Dialect;
+use crate::error::DatapumpError;
+use crate::headers::{self, ColumnName, HeaderPolicy};
+use crate::input::CsvInput;
+use crate::schema::{Schema, SchemaColumn};
+use crate::types::{self, ColumnType};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::BufReader;
+
+/// Rows examined to infer a schema when the job does not say otherwise.
+pub const DEFAULT_SAMPLE_ROWS: usize = 1000;
+
+/// Which rows of a file schema inference looks at. The more it sees, the
+/// less likely a value further on does not fit the inferred type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Sample {
+    /// The first `rows` rows.
+    First { rows: usize },
+    /// `rows` rows picked at random from across the file, which is read to
+    /// the end. The same file always gives the same sample.
+    Reservoir { rows: usize },
+    /// Every row.
+    Full,
+}
+
+impl Default for Sample {
+    fn default() -> Self {
+        Sample::First {
+            rows: DEFAULT_SAMPLE_ROWS,
+        }
+    }
+}
+
+impl Sample {
+    pub fn is_default(&self) -> bool {
+        *self == Sample::default()
+    }
+
+    pub fn validate(&self) -> Result<(), DatapumpError> {
+        match self {
+            Sample::First { rows: 0 } | Sample::Reservoir { rows: 0 } => Err(
+                DatapumpError::invalid("A schema sample needs at least one row"),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// "first 1000 rows", "reservoir of 1000 rows" or "full scan".
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sample::First { rows } => write!(f, "first {} rows", rows),
+            Sample::Reservoir { rows } => write!(f, "reservoir of {} rows", rows),
+            Sample::Full => write!(f, "full scan"),
+        }
+    }
+}
+
+/// The inferred schema of a CSV file and how the file was read.
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvSchema {
+    /// The columns, with their original headers, types and sampled values,
+    /// and the dialect the file was read in.
+    pub schema: Schema,
+    /// Name of the encoding, e.g. "UTF-8" or "windows-1252".
+    pub encoding: String,
+    /// How the file is compressed; it is decompressed as it is read.
+    pub compression: Compression,
+    /// How the rows the types were inferred from were picked.
+    pub sample: Sample,
+    /// Number of rows the types were inferred from, fewer than the sample
+    /// asked for when the file is shorter.
+    pub rows_sampled: u64,
+    /// Empty and distinct values of each column in the sample, in schema
+    /// order.
+    pub stats: Vec<ColumnStats>,
+    /// NOT NULL columns and primary keys the sample suggests.
+    pub constraints: SuggestedConstraints,
+}
+
+/// Infer the schema of `file_path`, read in `dialect`, naming its columns
+/// according to `policy` and typing them from the rows `sample` picks. The
+/// same rows are profiled for empty and distinct values to suggest
+/// constraints.
+pub fn get_csv_schema(
+    file_path: &str,
+    dialect: Dialect,
+    policy: HeaderPolicy,
+    sample: Sample,
+) -> Result<CsvSchema, DatapumpError> {
+    sample.validate()?;
+    let mut csv_reader = open_reader(file_path, &dialect)?;
+    let columns = column_names(&mut csv_reader, &dialect, policy)?;
+
+    let mut inferred = vec![InferredType::default(); columns.len()];
+    let mut profile = Profile::new(columns.len());
+    let mut widen = |record: &csv::StringRecord| {
+        for (column_type, field) in inferred.iter_mut().zip(record.iter()) {
+            *column_type = std::mem::take(column_type).widen(field);
+        }
+        profile.add(record);
+    };
+    // Reading stops at a record that cannot be read, which fails inference
+    let mut error = None;
+    let rows_sampled = {
+        let mut records = csv_reader
+            .records()
+            .map_while(|result| result.map_err(|e| error = Some(e)).ok())
+            .filter(|record| !record.is_empty());
+        match sample {
+            Sample::First { rows } => records
+                .by_ref()
+                .take(rows)
+                .map(|record| widen(&record))
+                .count(),
+            Sample::Full => records.map(|record| widen(&record)).count(),
+            Sample::Reservoir { rows } => {
+                let reservoir = reservoir_sample(records, rows);
+                reservoir.iter().for_each(&mut widen);
+                reservoir.len()
+            }
+        }
+    } as u64;
+    if let Some(e) = error {
+        let input = csv_reader.get_ref().get_ref();
+        return Err(DatapumpError::from(e).map_line(|line| input.file_line(line)));
+    }
+
+    let types: Vec<ColumnType> = columns
+        .iter()
+        .zip(inferred.iter())
+        .map(|(column, column_type)| column_type.column_type(&column.name, dialect.decimal))
+        .collect();
+    let schema_columns = columns
+        .iter()
+        .zip(&types)
+        .enumerate()
+        .map(|(i, (column, column_type))| SchemaColumn {
+            original: column.original.clone(),
+            nullable: profile.rows == 0 || profile.nulls[i] > 0,
+            samples: profile.samples[i].clone(),
+            confidence: inferred[i].confidence(
+                &column.name,
+                profile.rows - profile.nulls[i],
+                dialect.decimal,
+            ),
+            ..SchemaColumn::new(column.name.clone(), column_type)
+        })
+        .collect();
+
+    Ok(CsvSchema {
+        encoding: dialect.encoding.clone(),
+        compression: compression::detect(file_path)?,
+        stats: profile.stats(&columns),
+        constraints: profile.suggest(&columns, &types),
+        schema: Schema {
+            columns: schema_columns,
+            dialect: Some(dialect),
+        },
+        sample,
+        rows_sampled,
+    })
+}
+
+/// Columns looked at for primary keys made of two columns. Keys tend to
+/// come first, and each pair tracked costs about as much as a column.
+const KEY_PAIR_COLUMNS: usize = 8;
+
+/// Distinct values of each column kept to show what it holds.
+const SAMPLE_VALUES: usize = 5;
+
+/// How often each column of a sample is empty and how many distinct values
+/// it has, for suggesting constraints. Pairs of leading columns are tracked
+/// too, as candidates for a composite key. The first few distinct values of
+/// each column are kept as examples.
+struct Profile {
+    rows: u64,
+    nulls: Vec<u64>,
+    distinct: Vec<Cardinality>,
+    samples: Vec<Vec<String>>,
+    /// Hash of each field of the row being added, `None` where it is empty.
+    hashes: Vec<Option<u64>>,
+    /// Distinct pairs of values, until either value is empty or a pair
+    /// repeats, which rules the pair out as a key.
+    pairs: Vec<((usize, usize), Option<Cardinality>)>,
+}
+
+impl Profile {
+    fn new(width: usize) -> Profile {
+        let leading = width.min(KEY_PAIR_COLUMNS);
+        let pairs = (0..leading)
+            .flat_map(|a| (a + 1..leading).map(move |b| ((a, b), Some(Cardinality::default()))))
+            .collect();
+        Profile {
+            rows: 0,
+            nulls: vec![0; width],
+            distinct: vec![Cardinality::default(); width],
+            samples: vec![Vec::new(); width],
+            hashes: vec![None; width],
+            pairs,
+        }
+    }
+
+    fn add(&mut self, record: &csv::StringRecord) {
+        self.rows += 1;
+        for (i, distinct) in self.distinct.iter_mut().enumerate() {
+            self.hashes[i] = match record.get(i).unwrap_or_default() {
+                "" => {
+                    self.nulls[i] += 1;
+                    None
+                }
+                field => {
+                    let hash = cardinality::hash(field);
+                    // Counting is still exact while there are this few values
+                    if distinct.insert(hash) && self.samples[i].len() < SAMPLE_VALUES {
+                        self.samples[i].push(field.to_string());
+                    }
+                    Some(hash)
+                }
+            };
+        }
+        for ((a, b), pair) in &mut self.pairs {
+            let Some(distinct) = pair else {
+                continue;
+            };
+            let new = match (self.hashes[*a], self.hashes[*b]) {
+                (Some(a), Some(b)) => distinct.insert(cardinality::combine(a, b)),
+                _ => false,
+            };
+            if !new {
+                *pair = None;
+            }
+        }
+    }
+
+    fn stats(&self, columns: &[ColumnName]) -> Vec<ColumnStats> {
+        columns
+            .iter()
+            .zip(self.nulls.iter().zip(&self.distinct))
+            .map(|(column, (nulls, distinct))| ColumnStats {
+                name: column.name.clone(),
+                nulls: *nulls,
+                distinct: distinct.count(),
+                exact: distinct.is_exact(),
+            })
+            .collect()
+    }
+
+    /// NOT NULL for columns that were never empty. A key is a column whose
+    /// values never repeat, or failing that a pair of columns whose
+    /// combined values don't. Real numbers, booleans and JSON make poor
+    /// keys, so columns of those types are passed over.
+    fn suggest(&self, columns: &[ColumnName], types: &[ColumnType]) -> SuggestedConstraints {
+        if self.rows == 0 {
+            return SuggestedConstraints::default();
+        }
+        let keyable = |i: usize| {
+            self.nulls[i] == 0
+                && !matches!(
+                    types[i],
+                    ColumnType::Real(_) | ColumnType::Boolean | ColumnType::Json
+                )
+        };
+        let name = |i: usize| columns[i].name.clone();
+
+        let not_null = (0..columns.len())
+            .filter(|&i| self.nulls[i] == 0)
+            .map(name)
+            .collect();
+        let mut primary_keys: Vec<KeyCandidate> = (0..columns.len())
+            .filter(|&i| keyable(i) && self.distinct[i].all_distinct(self.rows))
+            .map(|i| KeyCandidate {
+                columns: vec![name(i)],
+                exact: self.distinct[i].is_exact(),
+            })
+            .collect();
+        if primary_keys.is_empty() {
+            primary_keys = self
+                .pairs
+                .iter()
+                .filter_map(|((a, b), pair)| {
+                    let distinct = pair.as_ref()?;
+                    (keyable(*a) && keyable(*b) && distinct.all_distinct(self.rows)).then(|| {
+                        KeyCandidate {
+                            columns: vec![name(*a), name(*b)],
+                            exact: distinct.is_exact(),
+                        }
+                    })
+                })
+                .collect();
+        }
+        SuggestedConstraints {
+            not_null,
+            primary_keys,
+        }
+    }
+}
+
+/// The kinds of value a column can hold, from which its type is inferred.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Kind {
+    #[default]
+    Empty,
+    Number,
+    Boolean,
+    Date,
+    Time,
+    Timestamp,
+    TimestampTz,
+    Uuid,
+    Json,
+    Text,
+}
+
+impl Kind {
+    /// Whether `value` is of this date or time kind in `format`.
+    fn parses(self, value: &str, format: &str) -> bool {
+        let format = Some(format);
+        match self {
+            Kind::Date => types::parse_date(value, format).is_some(),
+            Kind::Time => types::parse_time(value, format).is_some(),
+            Kind::Timestamp => types::parse_timestamp(value, format).is_some(),
+            Kind::TimestampTz => types::parse_timestamptz(value, format).is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// A bit for numbers as Rust writes them, such as "-1234.56" or "1e6",
+/// after one bit for each of `types::NUMBER_FORMATS`.
+const PLAIN: u8 = 1 << 7;
+
+/// The ways of writing numbers that every value of a column so far fits.
+#[derive(Debug, Clone, Copy, Default)]
+struct NumberFits {
+    /// `PLAIN`, and a bit for each number format, for those the values are
+    /// written in.
+    fits: u8,
+    /// The same, for those in which every value is also a whole number.
+    whole: u8,
+}
+
+impl NumberFits {
+    fn of(value: &str) -> Option<NumberFits> {
+        if value.parse::<i64>().is_ok() {
+            return Some(NumberFits {
+                fits: u8::MAX,
+                whole: u8::MAX,
+            });
+        }
+        if let Some(numbers) = NumberFits::of_decimal(value) {
+            return Some(numbers);
+        }
+        let mut numbers = NumberFits::default();
+        // Not "inf" or "NaN", which are more likely words than numbers
+        if value.parse::<f64>().is_ok() && value.contains(|c: char| c.is_ascii_digit()) {
+            numbers.fits |= PLAIN;
+        }
+        for (i, format) in types::NUMBER_FORMATS.iter().enumerate() {
+            if types::parse_real(value, Some(format)).is_some() {
+                numbers.fits |= 1 << i;
+                if types::parse_integer(value, Some(format)).is_some() {
+                    numbers.whole |= 1 << i;
+                }
+            }
+        }
+        (numbers.fits != 0).then_some(numbers)
+    }
+
+    /// The fits of a plain decimal such as "-12.50", the most common kind
+    /// of number, worked out from the separators of each format rather
+    /// than by parsing it in each. It fits any format with '.' as decimal
+    /// separator, and is a whole number in those that group thousands with
+    /// '.' if it reads as grouped, as "1.234" does.
+    fn of_decimal(value: &str) -> Option<NumberFits> {
+        let (integer, fraction) = value.strip_prefix('-').unwrap_or(value).split_once('.')?;
+        let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !digits(integer) || !digits(fraction) {
+            return None;
+        }
+        let whole = fraction.bytes().all(|b| b == b'0');
+        let grouped = integer.len() <= 3 && fraction.len() == 3;
+        let mut numbers = NumberFits {
+            fits: PLAIN,
+            whole: 0,
+        };
+        for (i, format) in types::NUMBER_FORMATS.iter().enumerate() {
+            match types::number_separators(format) {
+                Some(('.', _)) => {
+                    numbers.fits |= 1 << i;
+                    if whole {
+                        numbers.whole |= 1 << i;
+                    }
+                }
+                Some((_, Some('.'))) if grouped => {
+                    numbers.fits |= 1 << i;
+                    numbers.whole |= 1 << i;
+                }
+                _ => {}
+            }
+        }
+        Some(numbers)
+    }
+
+    fn and(self, other: NumberFits) -> NumberFits {
+        NumberFits {
+            fits: self.fits & other.fits,
+            whole: self.whole & other.whole,
+        }
+    }
+
+    /// Whether the values read as plain numbers, and if so whether they are
+    /// all whole. Plain numbers with a fraction are not read so when the
+    /// decimal separator is ','.
+    fn plain(&self, decimal: Option<char>) -> Option<bool> {
+        let whole = self.whole & PLAIN != 0;
+        (self.fits & PLAIN != 0 && (whole || decimal != Some(','))).then_some(whole)
+    }
+
+    /// The number formats the values fit with `decimal` as the decimal
+    /// separator, if it is given.
+    fn candidates(&self, decimal: Option<char>) -> u8 {
+        let allowed = types::NUMBER_FORMATS
+            .iter()
+            .enumerate()
+            .filter(|(_, format)| {
+                decimal.is_none() || types::number_separators(format).map(|(d, _)| d) == decimal
+            })
+            .fold(0u8, |mask, (i, _)| mask | 1 << i);
+        self.fits & allowed
+    }
+
+    /// Plain numbers, or numbers in the first format that fits, as integers
+    /// if every value is whole. Text when nothing fits.
+    fn column_type(&self, decimal: Option<char>) -> ColumnType {
+        if let Some(whole) = self.plain(decimal) {
+            return if whole {
+                ColumnType::Integer(None)
+            } else {
+                ColumnType::Real(None)
+            };
+        }
+        let candidates = self.candidates(decimal);
+        if candidates == 0 {
+            return ColumnType::Text;
+        }
+        let i = candidates.trailing_zeros();
+        let format = Some(types::NUMBER_FORMATS[i as usize].to_string());
+        if self.whole & (1 << i) != 0 {
+            ColumnType::Integer(format)
+        } else {
+            ColumnType::Real(format)
+        }
+    }
+
+    /// How many ways there are to read the values: two when they fit
+    /// formats with either decimal separator, as "1,234" does.
+    fn readings(&self, decimal: Option<char>) -> usize {
+        if self.plain(decimal).is_some() {
+            return 1;
+        }
+        let candidates = self.candidates(decimal);
+        let mut decimals: Vec<char> = types::NUMBER_FORMATS
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| candidates & (1 << i) != 0)
+            .filter_map(|(_, format)| types::number_separators(format).map(|(d, _)| d))
+            .collect();
+        decimals.dedup();
+        decimals.len().max(1)
+    }
+}
+
+/// The narrowest type that fits every value of a column seen so far.
+/// Integers widen to real; any other mix of kinds makes the column text.
+/// Empty values are NULL and fit any type.
+#[derive(Debug, Clone, Default)]
+struct InferredType {
+    kind: Kind,
+    /// For dates and times, the formats every value so far is in, in the
+    /// order they are preferred.
+    formats: Vec<&'static str>,
+    /// For numbers, the ways of writing them every value so far fits.
+    numbers: NumberFits,
+    /// Whether every value so far is 0 or 1.
+    zero_one: bool,
+}
+
+impl InferredType {
+    fn of(value: &str) -> InferredType {
+        if value.is_empty() {
+            return InferredType::default();
+        }
+        if let Some(numbers) = NumberFits::of(value) {
+            return InferredType {
+                kind: Kind::Number,
+                numbers,
+                zero_one: value == "0" || value == "1",
+                ..InferredType::default()
+            };
+        }
+        let kind = if types::is_bool_word(value) {
+            Kind::Boolean
+        } else if types::is_uuid(value) {
+            Kind::Uuid
+        } else if types::is_json(value) {
+            Kind::Json
+        } else {
+            return InferredType::of_date_or_time(value);
+        };
+        InferredType {
+            kind,
+            ..InferredType::default()
+        }
+    }
+
+    /// A date or time kind, with every format `value` is in, or text.
+    fn of_date_or_time(value: &str) -> InferredType {
+        let candidates = [
+            (Kind::TimestampTz, types::TIMESTAMPTZ_FORMATS),
+            (Kind::Timestamp, types::TIMESTAMP_FORMATS),
+            (Kind::Date, types::DATE_FORMATS),
+            (Kind::Time, types::TIME_FORMATS),
+        ];
+        for (kind, formats) in candidates {
+            let formats: Vec<&'static str> = formats
+                .iter()
+                .copied()
+                .filter(|format| kind.parses(value, format))
+                .collect();
+            if !formats.is_empty() {
+                return InferredType {
+                    kind,
+                    formats,
+                    ..InferredType::default()
+                };
+            }
+        }
+        InferredType {
+            kind: Kind::Text,
+            ..InferredType::default()
+        }
+    }
+
+    fn widen(mut self, value: &str) -> InferredType {
+        if value.is_empty() || self.kind == Kind::Text {
+            return self;
+        }
+        match self.kind {
+            Kind::Empty => InferredType::of(value),
+            Kind::Date | Kind::Time | Kind::Timestamp | Kind::TimestampTz => {
+                let kind = self.kind;
+                self.formats.retain(|format| kind.parses(value, format));
+                if self.formats.is_empty() {
+                    self.kind = Kind::Text;
+                }
+                self
+            }
+            _ => {
+                let other = InferredType::of(value);
+                self.kind = match (self.kind, other.kind) {
+                    (Kind::Number, Kind::Number) => {
+                        self.numbers = self.numbers.and(other.numbers);
+                        if self.numbers.fits == 0 {
+                            Kind::Text
+                        } else {
+                            Kind::Number
+                        }
+                    }
+                    (a, b) if a == b => a,
+                    _ => Kind::Text,
+                };
+                self.zero_one &= other.zero_one;
+                self
+            }
+        }
+    }
+
+    /// How sure the type of the column called `name` is, having seen
+    /// `values` values that were not empty, with `decimal` the decimal
+    /// separator if it is known. Each value adds certainty; a date or time
+    /// whose values fit several formats, numbers that read either way, or
+    /// 0s and 1s taken for a flag because of the column's name, are less
+    /// certain.
+    fn confidence(&self, name: &str, values: u64, decimal: Option<char>) -> f64 {
+        let evidence = values as f64 / (values + 1) as f64;
+        let readings = match self.kind {
+            Kind::Number if self.zero_one && looks_like_flag(name) => 2,
+            Kind::Number => self.numbers.readings(decimal),
+            _ => self.formats.len().max(1),
+        };
+        (evidence / readings as f64 * 100.0).round() / 100.0
+    }
+
+    /// The type of the column called `name`, with `decimal` the decimal
+    /// separator if it is known. A column with no values at all is text.
+    /// One of only 0s and 1s is boolean if its name suggests a flag.
+    fn column_type(&self, name: &str, decimal: Option<char>) -> ColumnType {
+        let format = || self.formats.first().map(|format| format.to_string());
+        match self.kind {
+            Kind::Empty | Kind::Text => ColumnType::Text,
+            Kind::Number if self.zero_one && looks_like_flag(name) => ColumnType::Boolean,
+            Kind::Number => self.numbers.column_type(decimal),
+            Kind::Boolean => ColumnType::Boolean,
+            Kind::Date => ColumnType::Date(format()),
+            Kind::Time => ColumnType::Time(format()),
+            Kind::Timestamp => ColumnType::Timestamp(format()),
+            Kind::TimestampTz => ColumnType::TimestampTz(format()),
+            Kind::Uuid => ColumnType::Uuid,
+            Kind::Json => ColumnType::Json,
+        }
+    }
+}
+
+/// Whether a column name reads like a flag: "is_active", "hasEmail",
+/// "deleted_flag" or "enabled".
+fn looks_like_flag(name: &str) -> bool {
+    let name = headers::snake_case(name);
+    ["is_", "has_", "can_"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+        || ["_flag", "_yn"].iter().any(|suffix| name.ends_with(suffix))
+        || matches!(
+            name.as_str(),
+            "active" | "enabled" | "disabled" | "deleted" | "flag" | "valid"
+        )
+}
+
+/// The type of the column called `name` that every value fits, as written
+/// in a schema, with `decimal` the decimal separator if it is known.
+pub(crate) fn infer_type<'a>(
+    name: &str,
+    values: impl Iterator<Item = &'a str>,
+    decimal: Option<char>,
+) -> String {
+    values
+        .fold(InferredType::default(), InferredType::widen)
+        .column_type(name, decimal)
+        .name()
+}
+
+/// Pick `size` items uniformly at random from `items`, reading all of them
+/// (Algorithm R). Seeded with a constant, so that inferring the schema of a
+/// file again gives the same result.
+fn reservoir_sample<T>(items: impl Iterator<Item = T>, size: usize) -> Vec<T> {
+    let mut rng = SplitMix64(0x5EED);
+    let mut reservoir = Vec::with_capacity(size.min(DEFAULT_SAMPLE_ROWS));
+    for (seen, item) in items.enumerate() {
+        if seen < size {
+            reservoir.push(item);
+        } else {
+            let slot = (rng.next() % (seen as u64 + 1)) as usize;
+            if slot < size {
+                reservoir[slot] = item;
+            }
+        }
+    }
+    reservoir
+}
+
+/// A small, fast pseudo-random generator; plenty for sampling rows.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// The column names of `file_path`, read in `dialect`: its header row made
+/// into names according to `policy`, or numbered names if it has none.
+pub fn read_columns(
+    file_path: &str,
+    dialect: &Dialect,
+    policy: HeaderPolicy,
+) -> Result<Vec<ColumnName>, DatapumpError> {
+    column_names(&mut open_reader(file_path, dialect)?, dialect, policy)
+}
+
+fn open_reader(
+    file_path: &str,
+    dialect: &Dialect,
+) -> Result<csv::Reader<BufReader<CsvInput>>, DatapumpError> {
+    let input = CsvInput::open(file_path, dialect)?;
+    Ok(dialect
+        .reader_builder()
+        .flexible(true)
+        // Headers are trimmed when normalized, keeping the original for the mapping
+        .trim(csv::Trim::Fields)
+        .from_reader(BufReader::new(input)))
+}
+
+fn column_names(
+    csv_reader: &mut csv::Reader<BufReader<CsvInput>>,
+    dialect: &Dialect,
+    policy: HeaderPolicy,
+) -> Result<Vec<ColumnName>, DatapumpError> {
+    // Without a header row the first row is data; number the columns instead
+    Ok(if dialect.has_header {
+        headers::normalize(csv_reader.headers()?, policy)
+    } else {
+        headers::generated(csv_reader.headers()?.len())
+    })
+}
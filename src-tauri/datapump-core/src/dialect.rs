@@ -0,0 +1,705 @@
+// src/dialect.rs
+
+use crate::encoding::{self, DecodedFile};
+use crate::error::{Context, DatapumpError};
+use crate::input::{self, LineFilter};
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Decoded text sampled from the start of a file to sniff its dialect.
+const SAMPLE_SIZE: u64 = 64 * 1024;
+
+/// Delimiters tried on every file, most likely first; ties go to the earlier one.
+const DELIMITERS: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+/// Lines looked at to find other punctuation that could be a delimiter.
+const CUSTOM_DELIMITER_LINES: usize = 20;
+
+/// Rows looked at to decide whether the first row is a header.
+const HEADER_SAMPLE_ROWS: usize = 50;
+
+/// Lines looked at to find where the header row starts.
+const PREAMBLE_SAMPLE_LINES: usize = 100;
+
+/// How a quote character is written inside a quoted field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Escape {
+    /// `"a ""quoted"" word"`, as in RFC 4180.
+    #[default]
+    Doubled,
+    /// `"a \"quoted\" word"`.
+    Backslash,
+}
+
+/// What ends a record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Terminator {
+    #[default]
+    Lf,
+    Crlf,
+    /// A lone carriage return, as written by classic Mac OS.
+    Cr,
+}
+
+/// Everything needed to read a delimited text file correctly.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dialect {
+    /// Name of the character encoding, e.g. "UTF-8" or "windows-1252".
+    pub encoding: String,
+    pub delimiter: char,
+    pub quote: char,
+    #[serde(default)]
+    pub escape: Escape,
+    #[serde(default)]
+    pub terminator: Terminator,
+    /// Whether the first row holds column names rather than data.
+    pub has_header: bool,
+    /// Lines before the header row, or the first row, such as a report title.
+    #[serde(default)]
+    pub skip_lines: usize,
+    /// Lines after the last row, such as totals.
+    #[serde(default)]
+    pub skip_footer: usize,
+    /// Lines starting with this prefix, e.g. "#", are ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Lines matching this regular expression are ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment_pattern: Option<String>,
+    /// The decimal separator of numbers, '.' or ','. Decides between the
+    /// two readings of a number such as "1.234"; when `None`, each column's
+    /// numbers are read whichever way fits them, preferring '.'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimal: Option<char>,
+}
+
+/// What is known about a file's dialect before it is sniffed. Whatever is
+/// left `None` is detected.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DialectOptions {
+    /// Character encoding label such as "windows-1252"; detected when
+    /// omitted or "auto".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Field delimiter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delimiter: Option<char>,
+    /// Whether the first row holds column names. Files without one get
+    /// columns named `col_1`, `col_2`, ...
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_header: Option<bool>,
+    /// Lines to skip before the header row; when omitted, leading lines with
+    /// far fewer fields than the rows below them are skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_lines: Option<usize>,
+    /// Lines to drop from the end of the file. Never detected, as only the
+    /// start of the file is sampled.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub skip_footer: usize,
+    /// Lines starting with this prefix, after any indentation, are ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Lines matching this regular expression are ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_pattern: Option<String>,
+    /// The decimal separator of numbers, '.' or ','; detected per column
+    /// when omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimal: Option<char>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+fn check_decimal(decimal: Option<char>) -> Result<(), DatapumpError> {
+    match decimal {
+        None | Some('.' | ',') => Ok(()),
+        Some(_) => Err(DatapumpError::invalid(
+            "Decimal separator must be '.' or ','",
+        )),
+    }
+}
+
+impl DialectOptions {
+    pub fn validate(&self) -> Result<(), DatapumpError> {
+        self.delimiter.map(ascii_byte).transpose()?;
+        encoding::for_label(self.encoding.as_deref())?;
+        if self.comment.as_deref() == Some("") {
+            return Err(DatapumpError::invalid("Comment prefix cannot be empty"));
+        }
+        input::comment_regex(self.comment_pattern.as_deref())?;
+        check_decimal(self.decimal)
+    }
+}
+
+impl Dialect {
+    /// Sniff the dialect of `file_path` from a sample of its start, keeping
+    /// whatever `options` fixes.
+    pub fn sniff(file_path: &str, options: &DialectOptions) -> Result<Dialect, DatapumpError> {
+        options.validate()?;
+        let encoding = encoding::resolve(file_path, options.encoding.as_deref())?;
+        let file =
+            DecodedFile::open(file_path, encoding).context("Failed to open file for sniffing")?;
+
+        let mut bytes = Vec::new();
+        file.take(SAMPLE_SIZE)
+            .read_to_end(&mut bytes)
+            .context("Failed to read file")?;
+        let complete = (bytes.len() as u64) < SAMPLE_SIZE;
+        let sample = String::from_utf8_lossy(&bytes);
+
+        let mut dialect = sniff_sample(&sample, complete, options)?;
+        dialect.encoding = encoding.name().to_string();
+        Ok(dialect)
+    }
+
+    /// The encoding to decode the file with.
+    pub fn encoding(&self) -> Result<&'static Encoding, DatapumpError> {
+        encoding::for_label(Some(&self.encoding))?
+            .ok_or_else(|| DatapumpError::invalid("Dialect has no encoding"))
+    }
+
+    /// Check a dialect that came from outside, e.g. from the UI.
+    pub fn validate(&self) -> Result<(), DatapumpError> {
+        ascii_byte(self.delimiter)?;
+        ascii_byte(self.quote)?;
+        if self.delimiter == self.quote {
+            return Err(DatapumpError::invalid(
+                "Delimiter and quote character must differ",
+            ));
+        }
+        input::comment_regex(self.comment_pattern.as_deref())?;
+        check_decimal(self.decimal)?;
+        self.encoding().map(|_| ())
+    }
+
+    /// Options that fix as much of this dialect as options can, so sniffing
+    /// the same file with them finds it again.
+    pub fn options(&self) -> DialectOptions {
+        DialectOptions {
+            encoding: Some(self.encoding.clone()),
+            delimiter: Some(self.delimiter),
+            has_header: Some(self.has_header),
+            skip_lines: Some(self.skip_lines),
+            skip_footer: self.skip_footer,
+            comment: self.comment.clone(),
+            comment_pattern: self.comment_pattern.clone(),
+            decimal: self.decimal,
+        }
+    }
+
+    /// A `csv` reader builder configured for this dialect.
+    pub fn reader_builder(&self) -> csv::ReaderBuilder {
+        let mut builder = csv::ReaderBuilder::new();
+        // Both are checked to be ASCII when the dialect is built or validated
+        builder
+            .delimiter(self.delimiter as u8)
+            .quote(self.quote as u8)
+            .has_headers(self.has_header)
+            .terminator(match self.terminator {
+                Terminator::Lf | Terminator::Crlf => csv::Terminator::CRLF,
+                Terminator::Cr => csv::Terminator::Any(b'\r'),
+            });
+        if self.escape == Escape::Backslash {
+            builder.escape(Some(b'\\')).double_quote(false);
+        }
+        builder
+    }
+}
+
+fn ascii_byte(c: char) -> Result<u8, DatapumpError> {
+    if c.is_ascii() && c != '\n' && c != '\r' {
+        Ok(c as u8)
+    } else {
+        Err(DatapumpError::invalid(format!(
+            "{:?} is not a valid delimiter or quote; it must be a single ASCII character",
+            c
+        )))
+    }
+}
+
+/// Sniff the dialect of a decoded sample, keeping whatever `options` fixes.
+/// `complete` says whether the sample is the whole file; if not, its last,
+/// possibly cut-off line is ignored. The encoding is left empty for the
+/// caller to fill in.
+pub fn sniff_sample(
+    sample: &str,
+    complete: bool,
+    options: &DialectOptions,
+) -> Result<Dialect, DatapumpError> {
+    let sample = if complete {
+        sample
+    } else {
+        // Cut after the last line break, unless that would leave nothing
+        match sample.rfind(['\n', '\r']) {
+            Some(end) => &sample[..=end],
+            None => sample,
+        }
+    };
+
+    let terminator = sniff_terminator(sample);
+    let mut dialect = Dialect {
+        encoding: String::new(),
+        delimiter: ',',
+        quote: '"',
+        escape: Escape::Doubled,
+        terminator,
+        has_header: true,
+        skip_lines: options.skip_lines.unwrap_or(0),
+        skip_footer: options.skip_footer,
+        comment: options.comment.clone(),
+        comment_pattern: options.comment_pattern.clone(),
+        decimal: options.decimal,
+    };
+    let delimiter = options.delimiter.map(ascii_byte).transpose()?;
+
+    // Comment lines and a known preamble say nothing about the dialect
+    let lines = kept_lines(sample, &dialect)?;
+    let text: String = lines.iter().map(|&(_, line)| line).collect();
+    let mut quote = sniff_quote(&text);
+    dialect.quote = quote as char;
+    dialect.delimiter =
+        delimiter.unwrap_or_else(|| sniff_delimiter(&text, quote, terminator)) as char;
+
+    if options.skip_lines.is_none() {
+        let start = sniff_preamble(&lines, &dialect);
+        if start > 0 {
+            dialect.skip_lines = lines[start].0;
+            // The preamble may have hidden the delimiter; look again without it
+            let text: String = lines[start..].iter().map(|&(_, line)| line).collect();
+            quote = sniff_quote(&text);
+            dialect.quote = quote as char;
+            if delimiter.is_none() {
+                dialect.delimiter = sniff_delimiter(&text, quote, terminator) as char;
+            }
+        }
+    }
+
+    let text: String = kept_lines(sample, &dialect)?
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect();
+    dialect.escape = sniff_escape(&text, quote, dialect.delimiter as u8);
+    dialect.has_header = sniff_header(&text, &dialect);
+    Ok(dialect)
+}
+
+/// The lines of the sample that `dialect` keeps, terminators included, each
+/// with its index among all lines.
+fn kept_lines<'a>(
+    sample: &'a str,
+    dialect: &Dialect,
+) -> Result<Vec<(usize, &'a str)>, DatapumpError> {
+    let terminator = match dialect.terminator {
+        Terminator::Lf | Terminator::Crlf => '\n',
+        Terminator::Cr => '\r',
+    };
+    let mut filter = LineFilter::new(dialect)?;
+    Ok(sample
+        .split_inclusive(terminator)
+        .enumerate()
+        .filter(|(_, line)| filter.keep(line.as_bytes()))
+        .collect())
+}
+
+/// Where the header row, or the first row, starts among `lines`: after any
+/// leading lines with at most half as many fields as most rows have, such as
+/// a title or an "Account: 123" line above a bank statement. A header with
+/// just one field fewer than the rows below it is still a header.
+fn sniff_preamble(lines: &[(usize, &str)], dialect: &Dialect) -> usize {
+    let fields = |line: &str| {
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(line.as_bytes());
+        reader
+            .records()
+            .next()
+            .and_then(Result::ok)
+            .map_or(0, |record| record.len())
+    };
+    let counts: Vec<usize> = lines
+        .iter()
+        .take(PREAMBLE_SAMPLE_LINES)
+        .map(|&(_, line)| fields(line))
+        .collect();
+
+    let mut frequency: HashMap<usize, usize> = HashMap::new();
+    for &count in &counts {
+        *frequency.entry(count).or_default() += 1;
+    }
+    let Some((&modal, _)) = frequency
+        .iter()
+        .max_by_key(|&(&fields, &matching)| (matching, fields))
+    else {
+        return 0;
+    };
+
+    let start = counts
+        .iter()
+        .position(|&count| count * 2 > modal)
+        .unwrap_or(0);
+    // Never skip the whole sample
+    if start + 1 >= counts.len() {
+        0
+    } else {
+        start
+    }
+}
+
+/// The most common line ending in the sample.
+fn sniff_terminator(sample: &str) -> Terminator {
+    let crlf = sample.matches("\r\n").count();
+    let lf = sample.matches('\n').count() - crlf;
+    let cr = sample.matches('\r').count() - crlf;
+
+    if crlf > lf && crlf >= cr {
+        Terminator::Crlf
+    } else if cr > lf {
+        Terminator::Cr
+    } else {
+        Terminator::Lf
+    }
+}
+
+/// Single quotes are only taken for the quote character when they, and not
+/// double quotes, open or close fields; apostrophes inside text do neither.
+fn sniff_quote(sample: &str) -> u8 {
+    if quotes_at_boundaries(sample, b'\'') > quotes_at_boundaries(sample, b'"') {
+        b'\''
+    } else {
+        b'"'
+    }
+}
+
+/// Count occurrences of `quote` right after a line start or a likely
+/// delimiter, or right before a line end or a likely delimiter.
+fn quotes_at_boundaries(sample: &str, quote: u8) -> usize {
+    let bytes = sample.as_bytes();
+    let is_boundary = |b: Option<&u8>| match b {
+        None => true,
+        Some(b) => matches!(b, b'\n' | b'\r') || DELIMITERS.contains(b),
+    };
+    (0..bytes.len())
+        .filter(|&i| bytes[i] == quote)
+        .filter(|&i| {
+            let before = i.checked_sub(1).map(|j| &bytes[j]);
+            is_boundary(before) || is_boundary(bytes.get(i + 1))
+        })
+        .count()
+}
+
+/// Pick the delimiter that splits the sample's rows most consistently into
+/// the same number of fields, trying the usual delimiters first and then any
+/// other punctuation that appears equally often on every line.
+fn sniff_delimiter(sample: &str, quote: u8, terminator: Terminator) -> u8 {
+    let mut candidates = DELIMITERS.to_vec();
+    candidates.extend(custom_delimiters(sample, quote));
+
+    // Ties go to the earlier candidate, so the usual delimiters win
+    let mut best: Option<(u8, f64)> = None;
+    for &candidate in &candidates {
+        let Some(consistency) = score(sample, candidate, quote, terminator) else {
+            continue;
+        };
+        if best.is_some_and(|(_, best)| consistency <= best) {
+            continue;
+        }
+        // A comma only ever found between digits is a decimal separator when
+        // something else splits the rows too
+        if candidate == b',' && is_decimal_comma(sample) {
+            let splits_otherwise = candidates[1..]
+                .iter()
+                .any(|&other| score(sample, other, quote, terminator).is_some());
+            if splits_otherwise {
+                continue;
+            }
+        }
+        best = Some((candidate, consistency));
+    }
+    best.map_or(b',', |(delimiter, _)| delimiter)
+}
+
+/// How consistently `delimiter` splits the sample: the share of rows with the
+/// most common field count. `None` if that count is one, i.e. it does not
+/// split rows at all.
+fn score(sample: &str, delimiter: u8, quote: u8, terminator: Terminator) -> Option<f64> {
+    let dialect = Dialect {
+        encoding: String::new(),
+        delimiter: delimiter as char,
+        quote: quote as char,
+        escape: Escape::Doubled,
+        terminator,
+        has_header: false,
+        skip_lines: 0,
+        skip_footer: 0,
+        comment: None,
+        comment_pattern: None,
+        decimal: None,
+    };
+    let mut reader = dialect
+        .reader_builder()
+        .flexible(true)
+        .from_reader(sample.as_bytes());
+
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    let mut rows = 0;
+    for record in reader.records() {
+        let Ok(record) = record else {
+            continue;
+        };
+        // Blank lines say nothing about the delimiter
+        if record.len() == 1 && record[0].is_empty() {
+            continue;
+        }
+        *counts.entry(record.len()).or_default() += 1;
+        rows += 1;
+    }
+
+    let (&fields, &matching) = counts
+        .iter()
+        .max_by_key(|&(&fields, &matching)| (matching, fields))?;
+    if fields < 2 {
+        return None;
+    }
+    Some(matching as f64 / rows as f64)
+}
+
+/// Punctuation other than the usual delimiters that occurs the same,
+/// non-zero number of times on each of the first lines, such as `~` or `^`.
+fn custom_delimiters(sample: &str, quote: u8) -> Vec<u8> {
+    let lines: Vec<&str> = sample
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(CUSTOM_DELIMITER_LINES)
+        .collect();
+    let Some(first) = lines.first() else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<u8> = first
+        .bytes()
+        .filter(|b| b.is_ascii_punctuation() || *b == b' ')
+        // Characters that commonly occur inside values
+        .filter(|b| !b".-/:_'\"\\ ".contains(b) && *b != quote && !DELIMITERS.contains(b))
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates.retain(|&c| {
+        let count = |line: &str| line.bytes().filter(|&b| b == c).count();
+        let expected = count(first);
+        lines.iter().all(|line| count(line) == expected)
+    });
+    candidates
+}
+
+/// Whether every comma in the sample sits between two digits, as in `1,5`.
+fn is_decimal_comma(sample: &str) -> bool {
+    let bytes = sample.as_bytes();
+    let mut commas = (0..bytes.len()).filter(|&i| bytes[i] == b',').peekable();
+    commas.peek().is_some()
+        && commas.all(|i| {
+            i > 0
+                && bytes[i - 1].is_ascii_digit()
+                && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)
+        })
+}
+
+/// Backslash escaping when quotes are escaped with backslashes and never
+/// doubled inside a field.
+fn sniff_escape(sample: &str, quote: u8, delimiter: u8) -> Escape {
+    let bytes = sample.as_bytes();
+    let is_boundary =
+        |b: Option<&u8>| b.map_or(true, |&b| matches!(b, b'\n' | b'\r') || b == delimiter);
+
+    let mut escaped = 0;
+    let mut doubled = 0;
+    for i in 0..bytes.len().saturating_sub(1) {
+        if bytes[i + 1] != quote {
+            continue;
+        }
+        if bytes[i] == b'\\' {
+            escaped += 1;
+        } else if bytes[i] == quote {
+            let before = i.checked_sub(1).map(|j| &bytes[j]);
+            // `\""` is an escaped quote followed by the closing one, and
+            // `,"",` an empty quoted field
+            let after_escape = before == Some(&b'\\');
+            let empty_field = is_boundary(before) && is_boundary(bytes.get(i + 2));
+            if !after_escape && !empty_field {
+                doubled += 1;
+            }
+        }
+    }
+
+    if escaped > 0 && doubled == 0 {
+        Escape::Backslash
+    } else {
+        Escape::Doubled
+    }
+}
+
+/// Vote, column by column, on whether the first row differs from the rows
+/// below it the way names differ from values: text above numbers, or a
+/// different length above values that all have the same length. Only a
+/// number above numbers counts against a header, so without evidence either
+/// way the first row is taken to be one.
+fn sniff_header(sample: &str, dialect: &Dialect) -> bool {
+    let mut reader = dialect
+        .reader_builder()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(sample.as_bytes());
+    let rows: Vec<csv::StringRecord> = reader
+        .records()
+        .filter_map(Result::ok)
+        .filter(|row| !(row.len() == 1 && row[0].is_empty()))
+        .take(HEADER_SAMPLE_ROWS + 1)
+        .collect();
+    let Some((first, body)) = rows.split_first() else {
+        return true;
+    };
+
+    let mut votes = 0i32;
+    for (column, name) in first.iter().map(str::trim).enumerate() {
+        let values: Vec<&str> = body
+            .iter()
+            .filter_map(|row| row.get(column))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+
+        if values.iter().all(|value| is_number(value)) {
+            votes += if is_number(name) { -1 } else { 1 };
+        } else {
+            let length = values[0].chars().count();
+            let same_length = values.iter().all(|value| value.chars().count() == length);
+            if same_length && name.chars().count() != length {
+                votes += 1;
+            }
+        }
+    }
+    votes >= 0
+}
+
+fn is_number(value: &str) -> bool {
+    value.parse::<f64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sniff(sample: &str) -> Dialect {
+        sniff_sample(sample, true, &DialectOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn usual_delimiters() {
+        assert_eq!(sniff("a,b,c\n1,2,3\n4,5,6\n").delimiter, ',');
+        assert_eq!(sniff("a\tb\tc\n1\t2\t3\n").delimiter, '\t');
+        assert_eq!(sniff("a|b|c\n1|2|3\n").delimiter, '|');
+        assert_eq!(sniff("a~b~c\nx~y~z\n").delimiter, '~');
+    }
+
+    #[test]
+    fn semicolon_wins_over_decimal_commas() {
+        let dialect = sniff("name;price\napple;1,50\npear;2,25\n");
+        assert_eq!(dialect.delimiter, ';');
+    }
+
+    #[test]
+    fn quoted_delimiters_do_not_count() {
+        let dialect = sniff("a;b\n\"x, y, z\";1\n\"p, q\";2\n");
+        assert_eq!(dialect.delimiter, ';');
+        assert_eq!(dialect.quote, '"');
+    }
+
+    #[test]
+    fn single_quotes_only_at_field_boundaries() {
+        assert_eq!(sniff("a,b\n'x, y',1\n'z',2\n").quote, '\'');
+        assert_eq!(sniff("a,b\nit's,1\nO'Neil,2\n").quote, '"');
+    }
+
+    #[test]
+    fn terminators() {
+        assert_eq!(sniff("a,b\r\n1,2\r\n").terminator, Terminator::Crlf);
+        assert_eq!(sniff("a,b\r1,2\r").terminator, Terminator::Cr);
+        assert_eq!(sniff("a,b\n1,2\n").terminator, Terminator::Lf);
+    }
+
+    #[test]
+    fn backslash_escape() {
+        let dialect = sniff("a,b\n\"say \\\"hi\\\"\",1\n\"x\",2\n");
+        assert_eq!(dialect.escape, Escape::Backslash);
+        assert_eq!(sniff("a,b\n\"say \"\"hi\"\"\",1\n").escape, Escape::Doubled);
+    }
+
+    #[test]
+    fn header_row() {
+        assert!(sniff("id,name\n1,apple\n2,pear\n").has_header);
+        assert!(!sniff("1,2.5\n2,3.5\n3,4.5\n").has_header);
+        // Names of one length above values of another
+        assert!(sniff("code,qty\nAB1,x\nCD2,y\n").has_header);
+    }
+
+    #[test]
+    fn preamble_is_skipped() {
+        let dialect = sniff("Sales report\nAccount: 123\nid;name;qty\n1;a;2\n2;b;3\n");
+        assert_eq!(dialect.skip_lines, 2);
+        assert_eq!(dialect.delimiter, ';');
+        assert!(dialect.has_header);
+    }
+
+    #[test]
+    fn comment_lines_are_ignored() {
+        let options = DialectOptions {
+            comment: Some("#".to_string()),
+            ..DialectOptions::default()
+        };
+        let dialect = sniff_sample("# a, b, c, d, e\nx;y\n1;2\n", true, &options).unwrap();
+        assert_eq!(dialect.delimiter, ';');
+        assert_eq!(dialect.comment.as_deref(), Some("#"));
+    }
+
+    #[test]
+    fn options_are_kept() {
+        let options = DialectOptions {
+            delimiter: Some(';'),
+            skip_lines: Some(0),
+            decimal: Some(','),
+            ..DialectOptions::default()
+        };
+        let dialect = sniff_sample("title\na,b\n1,2\n", true, &options).unwrap();
+        assert_eq!(dialect.delimiter, ';');
+        assert_eq!(dialect.skip_lines, 0);
+        assert_eq!(dialect.decimal, Some(','));
+    }
+
+    #[test]
+    fn cut_off_last_line_is_ignored() {
+        // Only the cut-off line would split on '|'
+        let sample = "a,b\n1,2\n3,4\n5|6|7|8|9";
+        let dialect = sniff_sample(sample, false, &DialectOptions::default()).unwrap();
+        assert_eq!(dialect.delimiter, ',');
+    }
+
+    #[test]
+    fn bad_delimiter_option_is_rejected() {
+        let options = DialectOptions {
+            delimiter: Some('é'),
+            ..DialectOptions::default()
+        };
+        assert!(sniff_sample("a,b\n", true, &options).is_err());
+    }
+}
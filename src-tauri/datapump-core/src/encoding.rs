@@ -0,0 +1,212 @@
+// src/encoding.rs
+
+use crate::compression::{self, Compression, Decompressed};
+use crate::error::{Context, DatapumpError};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Bytes sampled from the start of a file to detect its encoding.
+const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// Detect the character encoding of `file_path`, decompressed if need be.
+pub fn detect(file_path: &str) -> Result<&'static Encoding, DatapumpError> {
+    let file =
+        Decompressed::open(file_path).context("Failed to open file for encoding detection")?;
+    let mut sample = Vec::with_capacity(SAMPLE_SIZE);
+    file.take(SAMPLE_SIZE as u64)
+        .read_to_end(&mut sample)
+        .context("Failed to read file")?;
+    let complete = sample.len() < SAMPLE_SIZE;
+    Ok(detect_bytes(&sample, complete))
+}
+
+/// Detect the encoding of a sample from the start of a file, in order of
+/// confidence: a byte order mark, the NUL pattern of BOM-less UTF-16, valid
+/// UTF-8, and finally a statistical guess among legacy code pages.
+/// `complete` says whether the sample is the whole file.
+pub fn detect_bytes(sample: &[u8], complete: bool) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+    if let Some(encoding) = detect_utf16(sample) {
+        return encoding;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => return UTF_8,
+        // Only the last character was cut off by the end of the sample
+        Err(e) if e.error_len().is_none() && !complete => return UTF_8,
+        Err(_) => {}
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(sample, complete);
+    detector.guess(None, true)
+}
+
+/// Mostly-ASCII text in UTF-16 has a NUL in every other byte: the high byte
+/// of each code unit, odd for little-endian and even for big-endian.
+fn detect_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let units = sample.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let (mut even_nuls, mut odd_nuls) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        even_nuls += (pair[0] == 0) as usize;
+        odd_nuls += (pair[1] == 0) as usize;
+    }
+
+    // Text in other encodings has next to no NULs at all
+    let threshold = units * 2 / 5;
+    if odd_nuls > threshold && even_nuls * 10 < units {
+        Some(UTF_16LE)
+    } else if even_nuls > threshold && odd_nuls * 10 < units {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// The encoding to read `file_path` with: `label` when given, else the
+/// detected one.
+pub fn resolve(file_path: &str, label: Option<&str>) -> Result<&'static Encoding, DatapumpError> {
+    match for_label(label)? {
+        Some(encoding) => Ok(encoding),
+        None => detect(file_path),
+    }
+}
+
+/// Look up an encoding by WHATWG label, such as "windows-1252", "latin1" or
+/// "utf-16le". `None`, "" and "auto" ask for detection and return `None`.
+pub fn for_label(label: Option<&str>) -> Result<Option<&'static Encoding>, DatapumpError> {
+    match label.map(str::trim) {
+        None | Some("") | Some("auto") => Ok(None),
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .map(Some)
+            .ok_or_else(|| DatapumpError::invalid(format!("Unknown encoding '{}'", label))),
+    }
+}
+
+/// A file read as UTF-8, decompressed and transcoded on the fly as needed.
+///
+/// An uncompressed UTF-8 file is passed through untouched apart from its byte
+/// order mark, so invalid bytes still surface as parse errors and the file
+/// stays seekable. Compressed files and other encodings are streamed; those
+/// cannot seek, but report how much of the file on disk they consumed.
+pub struct DecodedFile {
+    encoding: &'static Encoding,
+    compression: Compression,
+    inner: Inner,
+}
+
+enum Inner {
+    Utf8 { file: File, bom_len: u64 },
+    Streamed(Box<dyn Read + Send>, Arc<AtomicU64>),
+}
+
+impl DecodedFile {
+    pub fn open(file_path: &str, encoding: &'static Encoding) -> Result<Self, DatapumpError> {
+        let compression = compression::detect(file_path)?;
+
+        let inner = if encoding == UTF_8 && compression == Compression::None {
+            let mut file = File::open(file_path).context("Failed to open file")?;
+            let mut bom = [0u8; 3];
+            let read = file.read(&mut bom).context("Failed to read file")?;
+            let bom_len = if bom[..read] == [0xEF, 0xBB, 0xBF] {
+                3
+            } else {
+                0
+            };
+            file.seek(SeekFrom::Start(bom_len))
+                .context("Failed to read file")?;
+            Inner::Utf8 { file, bom_len }
+        } else if encoding == UTF_8 {
+            let source = Decompressed::open(file_path)?;
+            let consumed = source.counter();
+            Inner::Streamed(Box::new(skip_bom(source)?), consumed)
+        } else {
+            let source = Decompressed::open(file_path)?;
+            let consumed = source.counter();
+            // A BOM, if any, is removed while decoding
+            let decoder = DecodeReaderBytesBuilder::new()
+                .encoding(Some(encoding))
+                .build(source);
+            Inner::Streamed(Box::new(decoder), consumed)
+        };
+
+        Ok(DecodedFile {
+            encoding,
+            compression,
+            inner,
+        })
+    }
+
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Whether the file is decompressed or transcoded as it is read, and so
+    /// cannot seek.
+    pub fn is_streamed(&self) -> bool {
+        matches!(self.inner, Inner::Streamed(..))
+    }
+
+    /// Bytes of the file on disk consumed so far, BOM included. Runs ahead of
+    /// the parser by whatever the decoders have buffered.
+    pub fn raw_bytes_read(&self) -> u64 {
+        match &self.inner {
+            Inner::Utf8 { .. } => 0,
+            Inner::Streamed(_, consumed) => consumed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Drop a UTF-8 byte order mark from the start of a stream.
+fn skip_bom(mut reader: impl Read + Send) -> Result<impl Read + Send, DatapumpError> {
+    let mut start = Vec::with_capacity(3);
+    reader
+        .by_ref()
+        .take(3)
+        .read_to_end(&mut start)
+        .context("Failed to read file")?;
+    if start == [0xEF, 0xBB, 0xBF] {
+        start.clear();
+    }
+    Ok(io::Cursor::new(start).chain(reader))
+}
+
+impl Read for DecodedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Inner::Utf8 { file, .. } => file.read(buf),
+            Inner::Streamed(reader, _) => reader.read(buf),
+        }
+    }
+}
+
+/// Offsets are relative to the text, i.e. after any byte order mark.
+impl Seek for DecodedFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match &mut self.inner {
+            Inner::Utf8 { file, bom_len } => {
+                let pos = match pos {
+                    SeekFrom::Start(offset) => SeekFrom::Start(offset + *bom_len),
+                    other => other,
+                };
+                Ok(file.seek(pos)?.saturating_sub(*bom_len))
+            }
+            Inner::Streamed(..) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek in a compressed or transcoded file",
+            )),
+        }
+    }
+}
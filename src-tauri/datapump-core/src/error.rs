@@ -0,0 +1,428 @@
+// src/error.rs
+
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// Everything that can go wrong in a migration, with enough context for the
+/// UI to tell the user what failed and where.
+///
+/// Serializes as `{ "code": "parse", "message": "...", ... }`, where `code` is
+/// stable and the remaining fields are present only when known.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DatapumpError {
+    /// Reading or writing a local file failed.
+    Io { message: String },
+    /// The database could not be reached, or the connection was lost.
+    Connection {
+        message: String,
+        sqlstate: Option<String>,
+    },
+    /// The database rejected the credentials.
+    Auth {
+        message: String,
+        sqlstate: Option<String>,
+    },
+    /// A row of the source could not be read.
+    Parse {
+        message: String,
+        line: Option<u64>,
+        column: Option<String>,
+        value: Option<String>,
+    },
+    /// A value could not be converted to its column's type. When reported by
+    /// PostgreSQL, `line` is the line of the COPY data rather than of the file.
+    TypeCoercion {
+        message: String,
+        line: Option<u64>,
+        column: Option<String>,
+        value: Option<String>,
+        sqlstate: Option<String>,
+    },
+    /// The destination rejected a row (NOT NULL, UNIQUE, foreign key, ...).
+    Constraint {
+        message: String,
+        constraint: Option<String>,
+        sqlstate: Option<String>,
+        detail: Option<String>,
+    },
+    /// Any other error reported by the destination database.
+    Database {
+        message: String,
+        sqlstate: Option<String>,
+        detail: Option<String>,
+    },
+    /// A spec, schema or command argument is invalid.
+    InvalidInput { message: String },
+    /// A bug or a crashed worker thread inside datapump itself.
+    Internal { message: String },
+    /// The job was cancelled by the user.
+    Cancelled,
+}
+
+impl DatapumpError {
+    pub fn io(message: impl Into<String>) -> Self {
+        DatapumpError::Io {
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        DatapumpError::InvalidInput {
+            message: message.into(),
+        }
+    }
+
+    pub fn parse(message: impl Into<String>, line: Option<u64>) -> Self {
+        DatapumpError::Parse {
+            message: message.into(),
+            line,
+            column: None,
+            value: None,
+        }
+    }
+
+    /// Stable identifier of the kind of error, for the frontend to match on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DatapumpError::Io { .. } => "io",
+            DatapumpError::Connection { .. } => "connection",
+            DatapumpError::Auth { .. } => "auth",
+            DatapumpError::Parse { .. } => "parse",
+            DatapumpError::TypeCoercion { .. } => "type_coercion",
+            DatapumpError::Constraint { .. } => "constraint",
+            DatapumpError::Database { .. } => "database",
+            DatapumpError::InvalidInput { .. } => "invalid_input",
+            DatapumpError::Internal { .. } => "internal",
+            DatapumpError::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, DatapumpError::Cancelled)
+    }
+
+    /// Prefix the message with what was being attempted, e.g. "Failed to create table".
+    pub fn context(mut self, context: &str) -> Self {
+        match &mut self {
+            DatapumpError::Io { message }
+            | DatapumpError::Connection { message, .. }
+            | DatapumpError::Auth { message, .. }
+            | DatapumpError::Parse { message, .. }
+            | DatapumpError::TypeCoercion { message, .. }
+            | DatapumpError::Constraint { message, .. }
+            | DatapumpError::Database { message, .. }
+            | DatapumpError::InvalidInput { message }
+            | DatapumpError::Internal { message } => {
+                *message = format!("{}: {}", context, message);
+            }
+            DatapumpError::Cancelled => {}
+        }
+        self
+    }
+
+    /// Renumber the line a row error points at, e.g. from the text the
+    /// parser saw to the lines of the file itself.
+    pub fn map_line(mut self, f: impl FnOnce(u64) -> u64) -> Self {
+        if let DatapumpError::Parse {
+            line: Some(line), ..
+        }
+        | DatapumpError::TypeCoercion {
+            line: Some(line), ..
+        } = &mut self
+        {
+            *line = f(*line);
+        }
+        self
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            DatapumpError::Io { message }
+            | DatapumpError::Connection { message, .. }
+            | DatapumpError::Auth { message, .. }
+            | DatapumpError::Parse { message, .. }
+            | DatapumpError::TypeCoercion { message, .. }
+            | DatapumpError::Constraint { message, .. }
+            | DatapumpError::Database { message, .. }
+            | DatapumpError::InvalidInput { message }
+            | DatapumpError::Internal { message } => message,
+            DatapumpError::Cancelled => "Migration cancelled by user",
+        }
+    }
+}
+
+impl fmt::Display for DatapumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatapumpError::Parse {
+                line,
+                column,
+                value,
+                ..
+            }
+            | DatapumpError::TypeCoercion {
+                line,
+                column,
+                value,
+                ..
+            } => {
+                if let Some(line) = line {
+                    write!(f, "Line {}", line)?;
+                    if let Some(column) = column {
+                        write!(f, ", column '{}'", column)?;
+                    }
+                    write!(f, ": ")?;
+                }
+                write!(f, "{}", self.message())?;
+                if let Some(value) = value {
+                    write!(f, " (value: {:?})", value)?;
+                }
+                Ok(())
+            }
+            _ => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl std::error::Error for DatapumpError {}
+
+/// Wire format shared by every variant.
+#[derive(Serialize)]
+struct SerializedError<'a> {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    constraint: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sqlstate: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<&'a str>,
+}
+
+impl Serialize for DatapumpError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut wire = SerializedError {
+            code: self.code(),
+            message: self.to_string(),
+            line: None,
+            column: None,
+            value: None,
+            constraint: None,
+            sqlstate: None,
+            detail: None,
+        };
+        match self {
+            DatapumpError::Connection { sqlstate, .. } | DatapumpError::Auth { sqlstate, .. } => {
+                wire.sqlstate = sqlstate.as_deref();
+            }
+            DatapumpError::Parse {
+                line,
+                column,
+                value,
+                ..
+            } => {
+                wire.line = *line;
+                wire.column = column.as_deref();
+                wire.value = value.as_deref();
+            }
+            DatapumpError::TypeCoercion {
+                line,
+                column,
+                value,
+                sqlstate,
+                ..
+            } => {
+                wire.line = *line;
+                wire.column = column.as_deref();
+                wire.value = value.as_deref();
+                wire.sqlstate = sqlstate.as_deref();
+            }
+            DatapumpError::Constraint {
+                constraint,
+                sqlstate,
+                detail,
+                ..
+            } => {
+                wire.constraint = constraint.as_deref();
+                wire.sqlstate = sqlstate.as_deref();
+                wire.detail = detail.as_deref();
+            }
+            DatapumpError::Database {
+                sqlstate, detail, ..
+            } => {
+                wire.sqlstate = sqlstate.as_deref();
+                wire.detail = detail.as_deref();
+            }
+            DatapumpError::Io { .. }
+            | DatapumpError::InvalidInput { .. }
+            | DatapumpError::Internal { .. }
+            | DatapumpError::Cancelled => {}
+        }
+        wire.serialize(serializer)
+    }
+}
+
+/// Attach context to any error that converts into a `DatapumpError`.
+pub trait Context<T> {
+    fn context(self, context: &str) -> Result<T, DatapumpError>;
+}
+
+impl<T, E: Into<DatapumpError>> Context<T> for Result<T, E> {
+    fn context(self, context: &str) -> Result<T, DatapumpError> {
+        self.map_err(|e| e.into().context(context))
+    }
+}
+
+impl From<std::io::Error> for DatapumpError {
+    fn from(e: std::io::Error) -> Self {
+        DatapumpError::io(e.to_string())
+    }
+}
+
+impl From<tokio::task::JoinError> for DatapumpError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        DatapumpError::Internal {
+            message: format!("Worker task failed: {}", e),
+        }
+    }
+}
+
+impl From<csv::Error> for DatapumpError {
+    fn from(e: csv::Error) -> Self {
+        let line = e.position().map(|pos| pos.line());
+        match e.kind() {
+            csv::ErrorKind::Io(_) => DatapumpError::io(e.to_string()),
+            csv::ErrorKind::Utf8 { err, .. } => DatapumpError::Parse {
+                message: "Invalid UTF-8".to_string(),
+                line,
+                // 1-based field number; sources that know the header replace it with the name
+                column: Some((err.field() + 1).to_string()),
+                value: None,
+            },
+            csv::ErrorKind::UnequalLengths {
+                expected_len, len, ..
+            } => DatapumpError::parse(
+                format!("Expected {} fields, found {}", expected_len, len),
+                line,
+            ),
+            _ => DatapumpError::parse(e.to_string(), line),
+        }
+    }
+}
+
+impl From<sqlite::Error> for DatapumpError {
+    fn from(e: sqlite::Error) -> Self {
+        let message = e.message.clone().unwrap_or_else(|| e.to_string());
+        // Primary result codes, see https://www.sqlite.org/rescode.html
+        match e.code {
+            Some(19) => DatapumpError::Constraint {
+                message,
+                constraint: None,
+                sqlstate: None,
+                detail: None,
+            },
+            Some(23) => DatapumpError::Auth {
+                message,
+                sqlstate: None,
+            },
+            Some(14) => DatapumpError::Io { message },
+            _ => DatapumpError::Database {
+                message,
+                sqlstate: None,
+                detail: None,
+            },
+        }
+    }
+}
+
+impl From<tokio_postgres::Error> for DatapumpError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        let Some(db) = e.as_db_error() else {
+            // No server response: the connection itself failed.
+            let message = e.to_string();
+            let is_io =
+                std::error::Error::source(&e).is_some_and(|source| source.is::<std::io::Error>());
+            return if is_io || e.is_closed() {
+                DatapumpError::Connection {
+                    message,
+                    sqlstate: None,
+                }
+            } else {
+                DatapumpError::Database {
+                    message,
+                    sqlstate: None,
+                    detail: None,
+                }
+            };
+        };
+
+        let message = db.message().to_string();
+        let code = db.code().code();
+        let sqlstate = Some(code.to_string());
+        let detail = db.detail().map(str::to_string);
+
+        // SQLSTATE classes, see https://www.postgresql.org/docs/current/errcodes-appendix.html
+        match &code[..2] {
+            "28" => DatapumpError::Auth { message, sqlstate },
+            "08" | "3D" | "57" if code != "57014" => {
+                DatapumpError::Connection { message, sqlstate }
+            }
+            "22" => {
+                let (line, column, value) = db.where_().map(parse_copy_context).unwrap_or_default();
+                DatapumpError::TypeCoercion {
+                    message,
+                    line,
+                    column,
+                    value,
+                    sqlstate,
+                }
+            }
+            "23" => DatapumpError::Constraint {
+                message,
+                constraint: db.constraint().map(str::to_string),
+                sqlstate,
+                detail,
+            },
+            _ => DatapumpError::Database {
+                message,
+                sqlstate,
+                detail,
+            },
+        }
+    }
+}
+
+/// Pull the line, column and value out of a COPY error context such as
+/// `COPY sales, line 3, column amount: "abc"`.
+fn parse_copy_context(context: &str) -> (Option<u64>, Option<String>, Option<String>) {
+    let Some(rest) = context.strip_prefix("COPY ") else {
+        return (None, None, None);
+    };
+    let mut line = None;
+    let mut column = None;
+    let mut value = None;
+
+    for part in rest.split(", ").skip(1) {
+        if let Some(n) = part.strip_prefix("line ") {
+            line = n
+                .split(|c: char| !c.is_ascii_digit())
+                .next()
+                .and_then(|n| n.parse().ok());
+        } else if let Some(col) = part.strip_prefix("column ") {
+            match col.split_once(": ") {
+                Some((name, v)) => {
+                    column = Some(name.to_string());
+                    value = Some(v.trim_matches('"').to_string());
+                }
+                None => column = Some(col.to_string()),
+            }
+        }
+    }
+    (line, column, value)
+}
@@ -0,0 +1,98 @@
+// src/files.rs
+
+use crate::archive;
+use crate::csv_schema;
+use crate::dialect::Dialect;
+use crate::error::DatapumpError;
+use crate::headers::HeaderPolicy;
+use std::path::Path;
+
+/// Column that records which file each row came from, when loading several
+/// files into one table.
+pub const SOURCE_FILE_COLUMN: &str = "_source_file";
+
+/// Whether `path` names something to read, as opposed to a pattern.
+fn exists(path: &str) -> bool {
+    Path::new(path).is_file() || archive::locate(path).is_ok_and(|member| member.is_some())
+}
+
+/// Whether `path` is a glob pattern rather than the name of a file.
+pub fn is_pattern(path: &str) -> bool {
+    !exists(path) && path.contains(['*', '?', '['])
+}
+
+/// The files `paths` refer to, in order. Each path is a file or a glob
+/// pattern such as "sales_2026-10-*.csv", whose matches are taken in sorted
+/// order. A file listed twice is read once.
+pub fn expand(paths: &[String]) -> Result<Vec<String>, DatapumpError> {
+    let mut files: Vec<String> = Vec::new();
+    for path in paths {
+        let matches = if !is_pattern(path) {
+            if !exists(path) {
+                return Err(DatapumpError::io(format!("File does not exist: {}", path)));
+            }
+            vec![path.clone()]
+        } else {
+            let pattern = glob::glob(path).map_err(|e| {
+                DatapumpError::invalid(format!("Invalid file pattern '{}': {}", path, e))
+            })?;
+            let mut matches: Vec<String> = pattern
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file())
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+            if matches.is_empty() {
+                return Err(DatapumpError::io(format!("No files match {}", path)));
+            }
+            matches.sort();
+            matches
+        };
+        for file in matches {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    if files.is_empty() {
+        return Err(DatapumpError::invalid("No files to load"));
+    }
+    Ok(files)
+}
+
+/// Check that every file has the columns of the first, by name and in the
+/// same order, before any of them is loaded. With `source_file_column`, no
+/// file may have a column of that name itself.
+pub fn check_headers(
+    files: &[(String, Dialect)],
+    policy: HeaderPolicy,
+    source_file_column: bool,
+) -> Result<(), DatapumpError> {
+    let mut expected: Option<(&str, Vec<String>)> = None;
+    for (file, dialect) in files {
+        let columns: Vec<String> = csv_schema::read_columns(file, dialect, policy)?
+            .into_iter()
+            .map(|column| column.name)
+            .collect();
+        if source_file_column && columns.iter().any(|name| name == SOURCE_FILE_COLUMN) {
+            return Err(DatapumpError::invalid(format!(
+                "{} already has a column named {}",
+                file, SOURCE_FILE_COLUMN
+            )));
+        }
+
+        match &expected {
+            None => expected = Some((file, columns)),
+            Some((first, names)) if *names != columns => {
+                return Err(DatapumpError::invalid(format!(
+                    "Columns of {} do not match {}: expected {}, found {}",
+                    file,
+                    first,
+                    names.join(", "),
+                    columns.join(", ")
+                )));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
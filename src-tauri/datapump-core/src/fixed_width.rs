@@ -0,0 +1,486 @@
+// src/fixed_width.rs
+
+use crate::checkpoint::SourcePosition;
+use crate::compression;
+use crate::csv_schema;
+use crate::dialect::{Dialect, DialectOptions, Escape, Terminator};
+use crate::encoding;
+use crate::error::{Context, DatapumpError};
+use crate::headers::{self, HeaderPolicy};
+use crate::input::CsvInput;
+use crate::source::{RawRow, Source};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+/// Lines looked at to find column boundaries, types and a header row.
+const SAMPLE_LINES: usize = 1000;
+
+/// Positions that must be blank in every line to separate two detected
+/// columns; a single one is more likely a space inside values, as in
+/// "Carol Ann".
+const MIN_GAP: usize = 2;
+
+/// A column of a fixed-width file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixedWidthColumn {
+    pub name: String,
+    /// Position of the column's first character in the line, from 1.
+    pub start: usize,
+    /// Width in characters.
+    pub length: usize,
+    /// Column type, e.g. "integer"; inferred from the file when omitted.
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub column_type: Option<String>,
+}
+
+impl FixedWidthColumn {
+    /// Parse "name:start:length" or "name:start:length:type", as given on
+    /// the command line. Commas or whitespace may separate the parts too.
+    pub fn parse(spec: &str) -> Result<Self, DatapumpError> {
+        let is_separator = |c: char| c == ':' || c == ',' || c.is_whitespace();
+        let mut parts: Vec<&str> = Vec::new();
+        let mut rest = spec;
+        while parts.len() < 3 {
+            rest = rest.trim_start_matches(is_separator);
+            let end = rest.find(is_separator).unwrap_or(rest.len());
+            if end == 0 {
+                break;
+            }
+            parts.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+        // The type is the rest of the line, as its format may hold separators
+        let rest = rest.trim_start_matches(is_separator).trim_end();
+        if !rest.is_empty() {
+            parts.push(rest);
+        }
+        let invalid = || {
+            DatapumpError::invalid(format!(
+                "Invalid column '{}'; expected name:start:length[:type]",
+                spec
+            ))
+        };
+        let (name, start, length, column_type) = match parts.as_slice() {
+            [name, start, length] => (name, start, length, None),
+            [name, start, length, column_type] => (name, start, length, Some(column_type)),
+            _ => return Err(invalid()),
+        };
+        Ok(FixedWidthColumn {
+            name: name.to_string(),
+            start: start.parse().map_err(|_| invalid())?,
+            length: length.parse().map_err(|_| invalid())?,
+            column_type: column_type.map(|t| t.to_string()),
+        })
+    }
+
+    /// Byte range of the column in `line`, or `None` when the line ends
+    /// before the column starts.
+    fn range(&self, line: &str, ascii: bool) -> Option<(usize, usize)> {
+        let first = self.start - 1;
+        if ascii {
+            return (first < line.len()).then(|| (first, line.len().min(first + self.length)));
+        }
+        let mut chars = line.char_indices().map(|(i, _)| i).skip(first);
+        let start = chars.next()?;
+        let end = chars.nth(self.length - 1).unwrap_or(line.len());
+        Some((start, end))
+    }
+}
+
+/// The columns of a fixed-width file, each with its type, and how the file
+/// is read.
+#[derive(Debug, Clone, Serialize)]
+pub struct FixedWidthLayout {
+    pub columns: Vec<FixedWidthColumn>,
+    pub dialect: Dialect,
+}
+
+impl FixedWidthLayout {
+    /// Columns as (name, type) pairs, in schema order.
+    pub fn schema(&self) -> Vec<(String, String)> {
+        self.columns
+            .iter()
+            .map(|column| {
+                let column_type = column.column_type.as_deref().unwrap_or("text");
+                (column.name.clone(), column_type.to_string())
+            })
+            .collect()
+    }
+}
+
+/// Check columns given by the user before reading any file with them.
+pub fn validate_columns(columns: &[FixedWidthColumn]) -> Result<(), DatapumpError> {
+    for column in columns {
+        if column.name.trim().is_empty() {
+            return Err(DatapumpError::invalid(
+                "Fixed-width column name cannot be empty",
+            ));
+        }
+        if column.start == 0 || column.length == 0 {
+            return Err(DatapumpError::invalid(format!(
+                "Column '{}' must start at position 1 or later and be at least 1 character wide",
+                column.name
+            )));
+        }
+    }
+    if let Some(column) = columns
+        .iter()
+        .enumerate()
+        .find(|(i, column)| columns[..*i].iter().any(|c| c.name == column.name))
+        .map(|(_, column)| column)
+    {
+        return Err(DatapumpError::invalid(format!(
+            "Column '{}' is defined twice",
+            column.name
+        )));
+    }
+    Ok(())
+}
+
+/// Read column definitions from a sidecar file: JSON or TOML (by extension)
+/// with a `columns` list, or text with one "name start length [type]" line
+/// per column. Blank lines, lines starting with "#" and a header line are
+/// ignored in text.
+pub fn load_columns(path: &str) -> Result<Vec<FixedWidthColumn>, DatapumpError> {
+    #[derive(Deserialize)]
+    struct ColumnsFile {
+        columns: Vec<FixedWidthColumn>,
+    }
+
+    let text =
+        std::fs::read_to_string(path).context(&format!("Failed to read column spec {}", path))?;
+    let invalid =
+        |e: String| DatapumpError::invalid(format!("Invalid column spec {}: {}", path, e));
+    let extension = Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+
+    let columns = match extension.as_deref() {
+        Some("json") => {
+            serde_json::from_str::<ColumnsFile>(&text)
+                .map_err(|e| invalid(e.to_string()))?
+                .columns
+        }
+        Some("toml") => {
+            toml::from_str::<ColumnsFile>(&text)
+                .map_err(|e| invalid(e.to_string()))?
+                .columns
+        }
+        _ => text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .enumerate()
+            .filter_map(|(i, line)| match FixedWidthColumn::parse(line) {
+                // A first line that does not parse names the fields
+                Err(_) if i == 0 => None,
+                column => Some(column),
+            })
+            .collect::<Result<_, _>>()?,
+    };
+    validate_columns(&columns)?;
+    Ok(columns)
+}
+
+/// How to read a fixed-width file as lines: `options` gives the encoding,
+/// the lines to skip and whether the first line is a header. Nothing is
+/// quoted, and lines are never detected as a preamble.
+fn plain_dialect(file_path: &str, options: &DialectOptions) -> Result<Dialect, DatapumpError> {
+    options.validate()?;
+    let encoding = encoding::resolve(file_path, options.encoding.as_deref())?;
+    Ok(Dialect {
+        encoding: encoding.name().to_string(),
+        delimiter: ',',
+        // NUL never occurs in text, so no line is taken to be quoted
+        quote: '\0',
+        escape: Escape::Doubled,
+        terminator: Terminator::Lf,
+        has_header: options.has_header.unwrap_or(false),
+        skip_lines: options.skip_lines.unwrap_or(0),
+        skip_footer: options.skip_footer,
+        comment: options.comment.clone(),
+        comment_pattern: options.comment_pattern.clone(),
+        decimal: options.decimal,
+    })
+}
+
+/// Work out the layout of the fixed-width file at `file_path` from a sample
+/// of its lines. `columns` are used as given, with any missing types
+/// inferred; when empty, column boundaries are detected from where every
+/// line has a space. A header row is detected unless `options` says
+/// whether there is one, and names detected columns according to `policy`.
+pub fn layout(
+    file_path: &str,
+    columns: &[FixedWidthColumn],
+    options: &DialectOptions,
+    policy: HeaderPolicy,
+) -> Result<FixedWidthLayout, DatapumpError> {
+    validate_columns(columns)?;
+    let mut dialect = plain_dialect(file_path, options)?;
+    let lines = sample_lines(file_path, &dialect)?;
+
+    let mut columns = if columns.is_empty() {
+        detect_columns(&lines)?
+    } else {
+        columns.to_vec()
+    };
+    if options.has_header.is_none() {
+        dialect.has_header = looks_like_header(&columns, &lines);
+    }
+
+    let (header, rows) = match lines.split_first() {
+        Some((header, rows)) if dialect.has_header => (Some(header), rows),
+        _ => (None, lines.as_slice()),
+    };
+    // Detected columns are named by the header, if there is one
+    if columns.iter().all(|column| column.name.is_empty()) {
+        let names = match header {
+            Some(header) => {
+                let fields = split(header, &columns);
+                headers::normalize(fields.iter().copied(), policy)
+            }
+            None => headers::generated(columns.len()),
+        };
+        for (column, name) in columns.iter_mut().zip(names) {
+            column.name = name.name;
+        }
+    }
+
+    for column in columns
+        .iter_mut()
+        .filter(|column| column.column_type.is_none())
+    {
+        let values = rows
+            .iter()
+            .map(|line| split(line, std::slice::from_ref(column))[0]);
+        column.column_type = Some(csv_schema::infer_type(
+            &column.name,
+            values,
+            dialect.decimal,
+        ));
+    }
+    Ok(FixedWidthLayout { columns, dialect })
+}
+
+/// Up to `SAMPLE_LINES` non-blank lines from the start of the file, without
+/// the lines the dialect skips.
+fn sample_lines(file_path: &str, dialect: &Dialect) -> Result<Vec<String>, DatapumpError> {
+    let input = BufReader::new(CsvInput::open(file_path, dialect)?);
+    let mut lines = Vec::new();
+    for line in input.lines() {
+        let line = line.context("Failed to read file")?;
+        let line = line.trim_end_matches(['\r', '\n']);
+        if !line.trim().is_empty() {
+            lines.push(line.to_string());
+        }
+        if lines.len() == SAMPLE_LINES {
+            break;
+        }
+    }
+    Ok(lines)
+}
+
+/// Columns found from whitespace alignment: runs of character positions
+/// where some line has text, separated by at least `MIN_GAP` positions blank
+/// in every line.
+/// Each column takes in the blanks after it, the first those before it too,
+/// and the last runs to the end of the longest line. Names are left empty.
+fn detect_columns(lines: &[String]) -> Result<Vec<FixedWidthColumn>, DatapumpError> {
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut used = vec![false; width];
+    for line in lines {
+        for (i, c) in line.chars().enumerate() {
+            if !c.is_whitespace() {
+                used[i] = true;
+            }
+        }
+    }
+
+    let mut starts = Vec::new();
+    let mut blanks = 0;
+    for (i, &used) in used.iter().enumerate() {
+        if !used {
+            blanks += 1;
+            continue;
+        }
+        if starts.is_empty() || blanks >= MIN_GAP {
+            starts.push(i);
+        }
+        blanks = 0;
+    }
+    // Values of the first column may be right-aligned, leaving blanks before it
+    if let Some(first) = starts.first_mut() {
+        *first = 0;
+    } else {
+        return Err(DatapumpError::invalid(
+            "No columns found; the file is empty",
+        ));
+    }
+    Ok(starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| FixedWidthColumn {
+            name: String::new(),
+            start: start + 1,
+            length: starts.get(n + 1).unwrap_or(&width) - start,
+            column_type: None,
+        })
+        .collect())
+}
+
+/// Whether the first line names the columns: some column that holds numbers
+/// in every other line holds something else in the first.
+fn looks_like_header(columns: &[FixedWidthColumn], lines: &[String]) -> bool {
+    let Some((first, rest)) = lines.split_first() else {
+        return false;
+    };
+    if rest.is_empty() {
+        return false;
+    }
+    let first = split(first, columns);
+    let rows: Vec<Vec<&str>> = rest.iter().map(|line| split(line, columns)).collect();
+    (0..columns.len()).any(|i| {
+        let numeric = |value: &str| value.parse::<f64>().is_ok();
+        !first[i].is_empty()
+            && !numeric(first[i])
+            && rows.iter().all(|row| row[i].is_empty() || numeric(row[i]))
+            && rows.iter().any(|row| !row[i].is_empty())
+    })
+}
+
+/// Cut `line` into the trimmed values of `columns`. A line that ends early
+/// gives empty values for the columns it does not reach.
+fn split<'a>(line: &'a str, columns: &[FixedWidthColumn]) -> Vec<&'a str> {
+    let ascii = line.is_ascii();
+    columns
+        .iter()
+        .map(|column| {
+            column
+                .range(line, ascii)
+                .map_or("", |(start, end)| line[start..end].trim())
+        })
+        .collect()
+}
+
+/// A fixed-width text file, cut into columns by character position.
+pub struct FixedWidthSource {
+    total_bytes: Option<u64>,
+    columns: Vec<(String, String)>,
+    layout: Vec<FixedWidthColumn>,
+    has_header: bool,
+    input: BufReader<CsvInput>,
+    line: String,
+    /// Bytes of text read so far.
+    parsed: u64,
+    /// Lines of text read so far, which the file's lines may outnumber when
+    /// some are skipped.
+    lines: u64,
+    /// Rows read so far, including the header.
+    records: u64,
+}
+
+impl FixedWidthSource {
+    /// Open `file_path` to be read as `layout` describes. `columns` gives the
+    /// name and type each column is loaded as, in the same order.
+    pub fn open(
+        file_path: &str,
+        layout: FixedWidthLayout,
+        columns: Vec<(String, String)>,
+    ) -> Result<Self, DatapumpError> {
+        let input = BufReader::new(CsvInput::open(file_path, &layout.dialect)?);
+        Ok(FixedWidthSource {
+            total_bytes: compression::input_size(file_path),
+            columns,
+            layout: layout.columns,
+            has_header: layout.dialect.has_header,
+            input,
+            line: String::new(),
+            parsed: 0,
+            lines: 0,
+            records: 0,
+        })
+    }
+
+    /// Read the next line that is not blank into `line`, returning `false`
+    /// at the end of the file.
+    fn next_line(&mut self) -> Result<bool, DatapumpError> {
+        loop {
+            self.line.clear();
+            let read = self.input.read_line(&mut self.line).map_err(|e| {
+                let line = self.input.get_ref().file_line(self.lines + 1);
+                DatapumpError::parse(e.to_string(), Some(line))
+            })?;
+            if read == 0 {
+                return Ok(false);
+            }
+            self.parsed += read as u64;
+            self.lines += 1;
+            if !self.line.trim().is_empty() {
+                self.records += 1;
+                return Ok(true);
+            }
+        }
+    }
+}
+
+impl Source for FixedWidthSource {
+    fn schema(&self) -> &[(String, String)] {
+        &self.columns
+    }
+
+    fn total_bytes(&self) -> Option<u64> {
+        self.total_bytes
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.input.get_ref().bytes_read(self.parsed)
+    }
+
+    fn position(&self) -> SourcePosition {
+        SourcePosition {
+            byte: self.bytes_read(),
+            line: self.lines + 1,
+            record: self.records,
+            ..SourcePosition::default()
+        }
+    }
+
+    /// As with a delimited file, a file that is compressed, transcoded or
+    /// has lines left out is read again from the start up to the position.
+    fn seek(&mut self, position: SourcePosition) -> Result<(), DatapumpError> {
+        if !self.input.get_ref().is_seekable() {
+            while self.records < position.record {
+                if !self.next_line()? {
+                    return Err(DatapumpError::invalid(
+                        "Checkpoint is past the end of the file",
+                    ));
+                }
+            }
+            return Ok(());
+        }
+
+        self.input
+            .seek(SeekFrom::Start(position.byte))
+            .context("Failed to seek to checkpoint")?;
+        self.parsed = position.byte;
+        self.lines = position.line.saturating_sub(1);
+        self.records = position.record;
+        Ok(())
+    }
+
+    fn next_row(&mut self) -> Result<Option<RawRow>, DatapumpError> {
+        if self.has_header && self.records == 0 && !self.next_line()? {
+            return Ok(None);
+        }
+        if !self.next_line()? {
+            return Ok(None);
+        }
+        let line = self.line.trim_end_matches(['\r', '\n']);
+        Ok(Some(RawRow::from(split(line, &self.layout))))
+    }
+}
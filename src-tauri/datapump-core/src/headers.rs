@@ -0,0 +1,222 @@
+// src/headers.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Longest identifier PostgreSQL keeps; longer ones are silently truncated,
+/// which would make distinct headers collide.
+pub const MAX_NAME_BYTES: usize = 63;
+
+/// How column names are derived from a file's header row. Names are always
+/// trimmed, made unique and shortened to `MAX_NAME_BYTES`; the rest is optional.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeaderPolicy {
+    /// Rewrite names in snake_case, e.g. "Order Date" becomes "order_date".
+    pub snake_case: bool,
+}
+
+impl HeaderPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == HeaderPolicy::default()
+    }
+}
+
+/// A column name as found in the file and as it will be created.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnName {
+    /// The header as written, or `None` when the file has no header row.
+    pub original: Option<String>,
+    pub name: String,
+}
+
+impl ColumnName {
+    /// Whether the header had to change; generated names are not renames.
+    pub fn is_renamed(&self) -> bool {
+        self.original
+            .as_ref()
+            .is_some_and(|original| *original != self.name)
+    }
+}
+
+/// Names for a file without a header row: `col_1`, `col_2`, ...
+pub fn generated(count: usize) -> Vec<ColumnName> {
+    (1..=count)
+        .map(|n| ColumnName {
+            original: None,
+            name: positional_name(n),
+        })
+        .collect()
+}
+
+/// Turn a header row into safe, unique column names.
+pub fn normalize<'a>(
+    headers: impl IntoIterator<Item = &'a str>,
+    policy: HeaderPolicy,
+) -> Vec<ColumnName> {
+    let mut used = HashSet::new();
+    headers
+        .into_iter()
+        .enumerate()
+        .map(|(index, original)| {
+            let mut name = clean(original);
+            if policy.snake_case {
+                name = snake_case(&name);
+            }
+            if name.is_empty() {
+                name = positional_name(index + 1);
+            }
+            let name = unique(truncate(&name, MAX_NAME_BYTES), &mut used);
+            ColumnName {
+                original: Some(original.to_string()),
+                name,
+            }
+        })
+        .collect()
+}
+
+fn positional_name(n: usize) -> String {
+    format!("col_{}", n)
+}
+
+/// Trim, and replace characters that break quoted identifiers: double
+/// quotes and control characters such as embedded line breaks.
+fn clean(header: &str) -> String {
+    header
+        .trim()
+        .chars()
+        .map(|c| if c == '"' || c.is_control() { '_' } else { c })
+        .collect()
+}
+
+/// "Order Date", "orderDate" and "ORDER-DATE" all become "order_date".
+pub(crate) fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+            continue;
+        }
+        // Break before an upper-case letter that starts a word: "orderDate",
+        // or "HTTPCode" before the "C"
+        if c.is_uppercase() && i > 0 && !out.is_empty() && !out.ends_with('_') {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out.trim_end_matches('_').to_string()
+}
+
+/// Cut `name` to at most `max` bytes without splitting a character.
+fn truncate(name: &str, max: usize) -> String {
+    let mut end = name.len().min(max);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    name[..end].to_string()
+}
+
+/// Append `_2`, `_3`, ... until the name differs from every name used so far,
+/// shortening it to keep within `MAX_NAME_BYTES`. Names are compared without
+/// regard to case, since SQLite does.
+fn unique(name: String, used: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut n = 1;
+    while !used.insert(candidate.to_lowercase()) {
+        n += 1;
+        let suffix = format!("_{}", n);
+        candidate = truncate(&name, MAX_NAME_BYTES - suffix.len()) + &suffix;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(headers: &[&str], policy: HeaderPolicy) -> Vec<String> {
+        normalize(headers.iter().copied(), policy)
+            .into_iter()
+            .map(|column| column.name)
+            .collect()
+    }
+
+    const SNAKE: HeaderPolicy = HeaderPolicy { snake_case: true };
+
+    #[test]
+    fn quotes_and_control_characters_are_replaced() {
+        assert_eq!(
+            names(
+                &[" id ", "a\"b", "x,y:z", "line\nbreak"],
+                HeaderPolicy::default()
+            ),
+            ["id", "a_b", "x,y:z", "line_break"]
+        );
+    }
+
+    #[test]
+    fn empty_headers_get_positional_names() {
+        assert_eq!(
+            names(&["a", "", "  "], HeaderPolicy::default()),
+            ["a", "col_2", "col_3"]
+        );
+    }
+
+    #[test]
+    fn duplicates_are_numbered_ignoring_case() {
+        assert_eq!(
+            names(&["Name", "name", "NAME", "name_2"], HeaderPolicy::default()),
+            ["Name", "name_2", "NAME_3", "name_2_2"]
+        );
+    }
+
+    #[test]
+    fn long_names_are_truncated_and_kept_unique() {
+        let long = "é".repeat(40);
+        let names = names(&[&long, &long], HeaderPolicy::default());
+        assert_eq!(names[0], "é".repeat(31));
+        assert!(names[1].ends_with("_2"));
+        assert!(names.iter().all(|name| name.len() <= MAX_NAME_BYTES));
+        assert_ne!(names[0], names[1]);
+    }
+
+    #[test]
+    fn snake_case_names() {
+        assert_eq!(
+            names(
+                &[
+                    "Order Date",
+                    "orderDate",
+                    "ORDER-DATE",
+                    "HTTPCode",
+                    "Total (€)"
+                ],
+                SNAKE
+            ),
+            [
+                "order_date",
+                "order_date_2",
+                "order_date_3",
+                "http_code",
+                "total"
+            ]
+        );
+        assert_eq!(snake_case("item2Price"), "item2_price");
+    }
+
+    #[test]
+    fn originals_are_kept() {
+        let columns = normalize(["Order Date", "id"], SNAKE);
+        assert_eq!(columns[0].original.as_deref(), Some("Order Date"));
+        assert!(columns[0].is_renamed());
+        assert!(!columns[1].is_renamed());
+        assert!(!generated(1)[0].is_renamed());
+    }
+}
@@ -0,0 +1,388 @@
+// src/input.rs
+
+use crate::dialect::{Dialect, Escape, Terminator};
+use crate::encoding::DecodedFile;
+use crate::error::{Context, DatapumpError};
+use regex::bytes::Regex;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+
+/// The decoded text of a delimited file as the CSV parser should see it:
+/// without the lines before the header, comment lines or footer lines the
+/// dialect says to drop.
+///
+/// When there is nothing to drop the file is passed straight through and
+/// stays seekable. Otherwise it is read line by line, which cannot seek.
+pub struct CsvInput {
+    inner: Inner,
+}
+
+enum Inner {
+    Direct(DecodedFile),
+    Filtered(Box<Filtered>),
+}
+
+struct Filtered {
+    reader: BufReader<DecodedFile>,
+    filter: LineFilter,
+    terminator: u8,
+    /// The last lines kept, held back until it is clear they are not the footer.
+    footer: VecDeque<Vec<u8>>,
+    skip_footer: usize,
+    line: Vec<u8>,
+    offset: usize,
+    /// Bytes of decoded text read, dropped lines included.
+    consumed: u64,
+    kept: u64,
+    /// Lines dropped so far, each time some were, with the number of lines
+    /// kept before them: enough to tell a kept line's place in the file.
+    dropped: Vec<(u64, u64)>,
+}
+
+impl CsvInput {
+    /// Open `file_path` to be read in `dialect`.
+    pub fn open(file_path: &str, dialect: &Dialect) -> Result<Self, DatapumpError> {
+        let file =
+            DecodedFile::open(file_path, dialect.encoding()?).context("Failed to open file")?;
+        let filter = LineFilter::new(dialect)?;
+
+        let inner = if filter.is_noop() && dialect.skip_footer == 0 {
+            Inner::Direct(file)
+        } else {
+            Inner::Filtered(Box::new(Filtered {
+                reader: BufReader::new(file),
+                filter,
+                terminator: match dialect.terminator {
+                    Terminator::Lf | Terminator::Crlf => b'\n',
+                    Terminator::Cr => b'\r',
+                },
+                footer: VecDeque::with_capacity(dialect.skip_footer + 1),
+                skip_footer: dialect.skip_footer,
+                line: Vec::new(),
+                offset: 0,
+                consumed: 0,
+                kept: 0,
+                dropped: Vec::new(),
+            }))
+        };
+        Ok(CsvInput { inner })
+    }
+
+    fn file(&self) -> &DecodedFile {
+        match &self.inner {
+            Inner::Direct(file) => file,
+            Inner::Filtered(filtered) => filtered.reader.get_ref(),
+        }
+    }
+
+    /// Whether the parser can seek back to a byte offset it reported.
+    pub fn is_seekable(&self) -> bool {
+        matches!(&self.inner, Inner::Direct(file) if !file.is_streamed())
+    }
+
+    /// Bytes of the file consumed so far, given the byte offset the parser
+    /// has reached in the text it was given.
+    pub fn bytes_read(&self, parsed: u64) -> u64 {
+        match &self.inner {
+            _ if self.file().is_streamed() => self.file().raw_bytes_read(),
+            Inner::Direct(_) => parsed,
+            Inner::Filtered(filtered) => filtered.consumed,
+        }
+    }
+
+    /// The 1-based line of the file that the parser's `line` came from.
+    pub fn file_line(&self, line: u64) -> u64 {
+        match &self.inner {
+            Inner::Direct(_) => line,
+            Inner::Filtered(filtered) => {
+                let kept_before = line.saturating_sub(1);
+                let before = filtered
+                    .dropped
+                    .partition_point(|&(kept, _)| kept <= kept_before);
+                let dropped = before.checked_sub(1).map_or(0, |i| filtered.dropped[i].1);
+                line + dropped
+            }
+        }
+    }
+}
+
+impl Read for CsvInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Inner::Direct(file) => file.read(buf),
+            Inner::Filtered(filtered) => filtered.read(buf),
+        }
+    }
+}
+
+impl Seek for CsvInput {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match &mut self.inner {
+            Inner::Direct(file) => file.seek(pos),
+            Inner::Filtered(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek in a file with skipped lines",
+            )),
+        }
+    }
+}
+
+impl Filtered {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.offset == self.line.len() && !self.next_line()? {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.line.len() - self.offset);
+        buf[..n].copy_from_slice(&self.line[self.offset..self.offset + n]);
+        self.offset += n;
+        Ok(n)
+    }
+
+    /// Load the next line to pass on, returning `false` at the end of the file.
+    fn next_line(&mut self) -> io::Result<bool> {
+        loop {
+            let mut line = Vec::new();
+            let read = self.reader.read_until(self.terminator, &mut line)?;
+            if read == 0 {
+                // Whatever is still held back is the footer
+                return Ok(false);
+            }
+            self.consumed += read as u64;
+            if !self.filter.keep(&line) {
+                match self.dropped.last_mut() {
+                    Some((kept, dropped)) if *kept == self.kept => *dropped += 1,
+                    last => {
+                        let dropped = last.map_or(0, |&mut (_, dropped)| dropped);
+                        self.dropped.push((self.kept, dropped + 1));
+                    }
+                }
+                continue;
+            }
+            self.kept += 1;
+
+            self.footer.push_back(line);
+            if self.footer.len() > self.skip_footer {
+                self.line = self.footer.pop_front().unwrap_or_default();
+                self.offset = 0;
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// Decides, one physical line at a time, which lines of a file hold records.
+///
+/// Dropped are the first `skip_lines` lines, and after those blank lines and
+/// comment lines. A line that starts inside a quoted field is always kept, as
+/// it continues a record.
+pub(crate) struct LineFilter {
+    skip_lines: usize,
+    comment: Option<Vec<u8>>,
+    pattern: Option<Regex>,
+    quote: u8,
+    backslash_escape: bool,
+    lines_seen: usize,
+    in_quotes: bool,
+}
+
+impl LineFilter {
+    pub(crate) fn new(dialect: &Dialect) -> Result<Self, DatapumpError> {
+        Ok(LineFilter {
+            skip_lines: dialect.skip_lines,
+            comment: dialect
+                .comment
+                .as_ref()
+                .map(|prefix| prefix.as_bytes().to_vec()),
+            pattern: comment_regex(dialect.comment_pattern.as_deref())?,
+            quote: dialect.quote as u8,
+            backslash_escape: dialect.escape == Escape::Backslash,
+            lines_seen: 0,
+            in_quotes: false,
+        })
+    }
+
+    /// Whether every line is kept as it is.
+    pub(crate) fn is_noop(&self) -> bool {
+        self.skip_lines == 0 && self.comment.is_none() && self.pattern.is_none()
+    }
+
+    /// Whether to keep `line`, the next line of the file with its terminator.
+    pub(crate) fn keep(&mut self, line: &[u8]) -> bool {
+        self.lines_seen += 1;
+        if self.lines_seen <= self.skip_lines {
+            return false;
+        }
+        if !self.in_quotes {
+            let text = trim_terminator(line);
+            if text.iter().all(u8::is_ascii_whitespace) || self.is_comment(text) {
+                return false;
+            }
+        }
+        self.track_quotes(line);
+        true
+    }
+
+    fn is_comment(&self, text: &[u8]) -> bool {
+        let start = text
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(text.len());
+        self.comment
+            .as_ref()
+            .is_some_and(|prefix| text[start..].starts_with(prefix))
+            || self.pattern.as_ref().is_some_and(|re| re.is_match(text))
+    }
+
+    /// Follow whether the line ends inside a quoted field.
+    fn track_quotes(&mut self, line: &[u8]) {
+        let mut bytes = line.iter();
+        while let Some(&b) = bytes.next() {
+            if b == self.quote {
+                self.in_quotes = !self.in_quotes;
+            } else if b == b'\\' && self.backslash_escape && self.in_quotes {
+                bytes.next();
+            }
+        }
+    }
+}
+
+/// Compile a comment pattern, reporting a bad one as invalid input.
+pub(crate) fn comment_regex(pattern: Option<&str>) -> Result<Option<Regex>, DatapumpError> {
+    pattern
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| {
+                DatapumpError::invalid(format!("Invalid comment pattern '{}': {}", pattern, e))
+            })
+        })
+        .transpose()
+}
+
+fn trim_terminator(line: &[u8]) -> &[u8] {
+    let end = line
+        .iter()
+        .rposition(|&b| b != b'\n' && b != b'\r')
+        .map_or(0, |i| i + 1);
+    &line[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dialect() -> Dialect {
+        Dialect {
+            encoding: "UTF-8".to_string(),
+            delimiter: ',',
+            quote: '"',
+            escape: Escape::Doubled,
+            terminator: Terminator::Lf,
+            has_header: true,
+            skip_lines: 0,
+            skip_footer: 0,
+            comment: None,
+            comment_pattern: None,
+            decimal: None,
+        }
+    }
+
+    fn commented() -> Dialect {
+        Dialect {
+            comment: Some("#".to_string()),
+            ..dialect()
+        }
+    }
+
+    /// The lines of `text` that `dialect` keeps.
+    fn kept(dialect: &Dialect, text: &str) -> Vec<String> {
+        let mut filter = LineFilter::new(dialect).unwrap();
+        text.split_inclusive('\n')
+            .filter(|line| filter.keep(line.as_bytes()))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn plain_dialect_is_noop() {
+        assert!(LineFilter::new(&dialect()).unwrap().is_noop());
+        let dialect = Dialect {
+            skip_lines: 1,
+            ..dialect()
+        };
+        assert!(!LineFilter::new(&dialect).unwrap().is_noop());
+    }
+
+    #[test]
+    fn skipped_and_blank_lines_are_dropped() {
+        let dialect = Dialect {
+            skip_lines: 2,
+            ..dialect()
+        };
+        assert_eq!(
+            kept(&dialect, "Report\n\na,b\n\n  \r\n1,2\n"),
+            ["a,b\n", "1,2\n"]
+        );
+    }
+
+    #[test]
+    fn comment_prefix_after_indentation() {
+        assert_eq!(
+            kept(&commented(), "# note\na,b\n   # indented\n1,#2\n"),
+            ["a,b\n", "1,#2\n"]
+        );
+    }
+
+    #[test]
+    fn comment_pattern() {
+        let dialect = Dialect {
+            comment_pattern: Some("^(Total|Page \\d+)".to_string()),
+            ..dialect()
+        };
+        assert_eq!(
+            kept(&dialect, "a,b\n1,2\nPage 2\nTotal,2\n"),
+            ["a,b\n", "1,2\n"]
+        );
+    }
+
+    #[test]
+    fn lines_inside_quoted_fields_are_kept() {
+        let text = "a,b\n1,\"first\n\n# not a comment\nlast\"\n# comment\n";
+        assert_eq!(
+            kept(&commented(), text),
+            [
+                "a,b\n",
+                "1,\"first\n",
+                "\n",
+                "# not a comment\n",
+                "last\"\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_quotes_do_not_close_fields() {
+        let dialect = Dialect {
+            escape: Escape::Backslash,
+            ..commented()
+        };
+        let text = "1,\"a \\\" b\n# still quoted\"\n# comment\n";
+        assert_eq!(
+            kept(&dialect, text),
+            ["1,\"a \\\" b\n", "# still quoted\"\n"]
+        );
+        // Doubled quotes toggle twice, leaving the field open
+        let text = "1,\"a \"\" b\n# still quoted\"\n# comment\n";
+        assert_eq!(
+            kept(&commented(), text),
+            ["1,\"a \"\" b\n", "# still quoted\"\n"]
+        );
+    }
+
+    #[test]
+    fn bad_comment_pattern_is_rejected() {
+        let dialect = Dialect {
+            comment_pattern: Some("(".to_string()),
+            ..dialect()
+        };
+        assert!(LineFilter::new(&dialect).is_err());
+    }
+}
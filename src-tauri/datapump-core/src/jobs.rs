@@ -0,0 +1,180 @@
+// src/jobs.rs
+
+use crate::error::DatapumpError;
+use crate::progress::{ProgressEvent, Stage};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::Notify;
+
+/// Snapshot of a job's state, returned by `get_job_status` / `list_jobs`.
+#[derive(Debug, Serialize, Clone)]
+pub struct JobStatus {
+    pub job_id: String,
+    /// "running", "paused", "complete", "cancelled" or "failed".
+    pub state: String,
+    /// Last stage reported by the job.
+    pub stage: Stage,
+    pub processed_rows: usize,
+    pub bytes_read: u64,
+    pub total_bytes: Option<u64>,
+    pub message: Option<String>,
+}
+
+/// A running (or finished) migration with its own cancellation and pause tokens.
+pub struct Job {
+    id: String,
+    cancelled: AtomicBool,
+    paused: AtomicBool,
+    /// Wakes a paused job when it is resumed or cancelled.
+    wake: Notify,
+    status: Mutex<JobStatus>,
+}
+
+impl Job {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.wake.notify_one();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Ask the job to stop loading after the batch in flight is committed.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Let a paused job carry on loading.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        self.wake.notify_one();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the job is resumed or cancelled. May also return spuriously,
+    /// so callers re-check `is_paused`.
+    pub async fn woken(&self) {
+        self.wake.notified().await;
+    }
+
+    pub fn status(&self) -> JobStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Whether the job has not finished yet; a paused job is still running.
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.status.lock().unwrap().state.as_str(),
+            "running" | "paused"
+        )
+    }
+
+    /// Record the latest progress event in the job's live status.
+    pub fn update(&self, event: &ProgressEvent) {
+        let mut status = self.status.lock().unwrap();
+        status.stage = event.stage;
+        status.processed_rows = event.processed_rows;
+        status.bytes_read = event.bytes_read;
+        status.total_bytes = event.total_bytes;
+        status.message = event.message.clone();
+        status.state = match event.stage {
+            Stage::Done => "complete",
+            Stage::Failed => "failed",
+            Stage::Cancelled => "cancelled",
+            Stage::Paused => "paused",
+            _ => "running",
+        }
+        .to_string();
+    }
+
+    /// Mark the job as finished with the outcome of its command.
+    pub fn finish<T>(&self, result: &Result<T, DatapumpError>) {
+        let mut status = self.status.lock().unwrap();
+        match result {
+            Ok(_) if status.state == "running" => status.state = "complete".to_string(),
+            Ok(_) => {}
+            Err(_) if !matches!(status.state.as_str(), "running" | "paused") => {}
+            Err(e) => {
+                status.state = if e.is_cancelled() {
+                    "cancelled"
+                } else {
+                    "failed"
+                }
+                .to_string();
+                status.message = Some(e.to_string());
+            }
+        }
+    }
+}
+
+/// All jobs started in this process, keyed by job id.
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, Arc<Job>>>,
+}
+
+static REGISTRY: OnceLock<JobRegistry> = OnceLock::new();
+
+pub fn registry() -> &'static JobRegistry {
+    REGISTRY.get_or_init(JobRegistry::default)
+}
+
+impl JobRegistry {
+    /// Register a new job. The caller may choose the id (so it can listen for
+    /// progress and cancel before the command returns); otherwise one is generated.
+    pub fn start(&self, job_id: Option<String>) -> Result<Arc<Job>, DatapumpError> {
+        let id = job_id
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.get(&id).is_some_and(|job| job.is_running()) {
+            return Err(DatapumpError::invalid(format!(
+                "Job {} is already running",
+                id
+            )));
+        }
+
+        let job = Arc::new(Job {
+            id: id.clone(),
+            cancelled: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            wake: Notify::new(),
+            status: Mutex::new(JobStatus {
+                job_id: id.clone(),
+                state: "running".to_string(),
+                stage: Stage::Connecting,
+                processed_rows: 0,
+                bytes_read: 0,
+                total_bytes: None,
+                message: None,
+            }),
+        });
+        jobs.insert(id, job.clone());
+
+        Ok(job)
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<Arc<Job>> {
+        self.jobs.lock().unwrap().get(job_id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<JobStatus> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|job| job.status())
+            .collect()
+    }
+}
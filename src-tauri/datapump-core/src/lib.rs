@@ -0,0 +1,32 @@
+//! The datapump migration engine, independent of any UI.
+//!
+//! The Tauri app and the `datapump` CLI are thin layers over this crate.
+
+pub mod archive;
+pub mod cardinality;
+pub mod checkpoint;
+pub mod compression;
+pub mod constraints;
+pub mod csv_reader;
+pub mod csv_schema;
+pub mod dialect;
+pub mod encoding;
+pub mod error;
+pub mod files;
+pub mod fixed_width;
+pub mod headers;
+pub mod input;
+pub mod jobs;
+pub mod migrate;
+pub mod pipeline;
+pub mod postgres_copy;
+pub mod postgres_writer;
+pub mod progress;
+pub mod query;
+pub mod ragged;
+pub mod schema;
+pub mod sink;
+pub mod source;
+pub mod spec;
+pub mod sqlite_writer;
+pub mod types;
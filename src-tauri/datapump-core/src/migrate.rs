@@ -0,0 +1,537 @@
+// src/migrate.rs
+
+use crate::archive;
+use crate::checkpoint::Checkpoint;
+use crate::csv_schema::{self, Sample};
+use crate::dialect::{Dialect, DialectOptions};
+use crate::error::DatapumpError;
+use crate::files::{self, SOURCE_FILE_COLUMN};
+use crate::fixed_width::{self, FixedWidthColumn, FixedWidthSource};
+use crate::headers::HeaderPolicy;
+use crate::jobs;
+use crate::pipeline;
+use crate::progress::{ProgressSink, Stage, Tracker};
+use crate::ragged::RaggedRowPolicy;
+use crate::schema::Schema;
+use crate::sink::{PostgresSink, Sink, SqliteSink};
+use crate::source::{CsvFilesSource, CsvSource, Source};
+use crate::spec::JobSpec;
+use serde::{Deserialize, Serialize};
+
+/// Where a migration reads its records from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SourceConfig {
+    Csv {
+        path: String,
+        /// Encoding, delimiter, header row and lines to skip, where
+        /// detection should not decide.
+        #[serde(flatten)]
+        dialect: DialectOptions,
+        /// How header names are turned into column names.
+        #[serde(default, skip_serializing_if = "HeaderPolicy::is_default")]
+        headers: HeaderPolicy,
+    },
+    /// Several CSV/TSV files with the same columns, loaded into one table.
+    CsvFiles {
+        /// Files or glob patterns, e.g. "sales_2026-10-*.csv".
+        paths: Vec<String>,
+        /// How to read the files; each is sniffed for the rest.
+        #[serde(flatten)]
+        dialect: DialectOptions,
+        #[serde(default, skip_serializing_if = "HeaderPolicy::is_default")]
+        headers: HeaderPolicy,
+        /// Add a `_source_file` column with the file each row came from.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        source_file_column: bool,
+    },
+    /// A fixed-width text file, cut into columns by character position.
+    FixedWidth {
+        path: String,
+        /// Where each column is; detected from the file's whitespace when
+        /// neither these nor `columns_file` are given.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        columns: Vec<FixedWidthColumn>,
+        /// A JSON, TOML or text file with the columns, instead of `columns`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        columns_file: Option<String>,
+        /// Encoding, header row and lines to skip; the delimiter is unused.
+        #[serde(flatten)]
+        dialect: DialectOptions,
+        /// How header names are turned into names of detected columns.
+        #[serde(default, skip_serializing_if = "HeaderPolicy::is_default")]
+        headers: HeaderPolicy,
+    },
+}
+
+impl SourceConfig {
+    /// Shorthand for a CSV/TSV source with a detected dialect.
+    pub fn csv(path: impl Into<String>) -> Self {
+        SourceConfig::Csv {
+            path: path.into(),
+            dialect: DialectOptions::default(),
+            headers: HeaderPolicy::default(),
+        }
+    }
+
+    /// A CSV/TSV source for `paths`, each a file or a glob pattern: a single
+    /// file is a `Csv` source, anything else a `CsvFiles` one.
+    pub fn csv_files(
+        paths: Vec<String>,
+        dialect: DialectOptions,
+        headers: HeaderPolicy,
+        source_file_column: bool,
+    ) -> Self {
+        match paths.as_slice() {
+            [path] if !source_file_column && !files::is_pattern(path) => SourceConfig::Csv {
+                path: path.clone(),
+                dialect,
+                headers,
+            },
+            _ => SourceConfig::CsvFiles {
+                paths,
+                dialect,
+                headers,
+                source_file_column,
+            },
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DatapumpError> {
+        match self {
+            SourceConfig::Csv { path, dialect, .. } => {
+                // A zip member exists if its archive has it, checked on opening
+                if archive::locate(path)?.is_none() && !std::path::Path::new(path).exists() {
+                    return Err(DatapumpError::io(format!("File does not exist: {}", path)));
+                }
+                dialect.validate()
+            }
+            SourceConfig::CsvFiles { paths, dialect, .. } => {
+                files::expand(paths)?;
+                dialect.validate()
+            }
+            SourceConfig::FixedWidth {
+                path,
+                columns,
+                columns_file,
+                dialect,
+                ..
+            } => {
+                if !std::path::Path::new(path).exists() && archive::locate(path)?.is_none() {
+                    return Err(DatapumpError::io(format!("File does not exist: {}", path)));
+                }
+                if !columns.is_empty() && columns_file.is_some() {
+                    return Err(DatapumpError::invalid(
+                        "Give fixed-width columns either inline or in a file, not both",
+                    ));
+                }
+                fixed_width::validate_columns(columns)?;
+                dialect.validate()
+            }
+        }
+    }
+
+    /// The same source with what is found out about it fixed: glob patterns
+    /// replaced by the files they match now, and a fixed-width layout
+    /// detected or read from its file. A resumed job then reads the same
+    /// files in the same way.
+    pub(crate) fn resolved(&self) -> Result<SourceConfig, DatapumpError> {
+        match self {
+            SourceConfig::CsvFiles {
+                paths,
+                dialect,
+                headers,
+                source_file_column,
+            } => Ok(SourceConfig::CsvFiles {
+                paths: files::expand(paths)?,
+                dialect: dialect.clone(),
+                headers: *headers,
+                source_file_column: *source_file_column,
+            }),
+            SourceConfig::FixedWidth {
+                path,
+                dialect,
+                headers,
+                ..
+            } => {
+                let layout = self.fixed_width_layout()?;
+                Ok(SourceConfig::FixedWidth {
+                    path: path.clone(),
+                    columns: layout.columns,
+                    columns_file: None,
+                    dialect: DialectOptions {
+                        has_header: Some(layout.dialect.has_header),
+                        ..dialect.clone()
+                    },
+                    headers: *headers,
+                })
+            }
+            source => Ok(source.clone()),
+        }
+    }
+
+    /// How a fixed-width source is laid out: its columns as given, read
+    /// from its columns file or detected, each with a type.
+    fn fixed_width_layout(&self) -> Result<fixed_width::FixedWidthLayout, DatapumpError> {
+        let SourceConfig::FixedWidth {
+            path,
+            columns,
+            columns_file,
+            dialect,
+            headers,
+        } = self
+        else {
+            return Err(DatapumpError::invalid("Not a fixed-width source"));
+        };
+        let columns = match columns_file {
+            Some(columns_file) => fixed_width::load_columns(columns_file)?,
+            None => columns.clone(),
+        };
+        fixed_width::layout(path, &columns, dialect, *headers)
+    }
+
+    /// How `path` is to be read: as `given`, the dialect a schema records,
+    /// when there is one, or else sniffed honouring what the config fixes.
+    fn dialect(&self, path: &str, given: Option<&Dialect>) -> Result<Dialect, DatapumpError> {
+        if let Some(dialect) = given {
+            dialect.validate()?;
+            return Ok(dialect.clone());
+        }
+        match self {
+            SourceConfig::Csv { dialect, .. } | SourceConfig::CsvFiles { dialect, .. } => {
+                Dialect::sniff(path, dialect)
+            }
+            SourceConfig::FixedWidth { .. } => Ok(self.fixed_width_layout()?.dialect),
+        }
+    }
+
+    /// Open the source to read `columns`, in `dialect` when the schema
+    /// gives one. Every file of a `CsvFiles` source is read in it then.
+    fn open(
+        &self,
+        columns: Vec<(String, String)>,
+        dialect: Option<&Dialect>,
+        ragged: &RaggedRowPolicy,
+    ) -> Result<Box<dyn Source>, DatapumpError> {
+        self.validate()?;
+        match self {
+            SourceConfig::Csv { path, .. } => {
+                let dialect = self.dialect(path, dialect)?;
+                Ok(Box::new(CsvSource::open(path, &dialect, columns, ragged)?))
+            }
+            SourceConfig::CsvFiles {
+                paths,
+                headers,
+                source_file_column,
+                ..
+            } => {
+                let files = files::expand(paths)?
+                    .into_iter()
+                    .map(|path| {
+                        let dialect = self.dialect(&path, dialect)?;
+                        Ok((path, dialect))
+                    })
+                    .collect::<Result<Vec<_>, DatapumpError>>()?;
+                // Every file is checked before any row is loaded
+                files::check_headers(&files, *headers, *source_file_column)?;
+                Ok(Box::new(CsvFilesSource::open(
+                    files,
+                    columns,
+                    *source_file_column,
+                    ragged,
+                )?))
+            }
+            SourceConfig::FixedWidth { path, .. } => {
+                let mut layout = self.fixed_width_layout()?;
+                if dialect.is_some() {
+                    layout.dialect = self.dialect(path, dialect)?;
+                }
+                Ok(Box::new(FixedWidthSource::open(path, layout, columns)?))
+            }
+        }
+    }
+
+    /// The schema of the source's file, or of the first of its files, with
+    /// the types of a delimited file inferred from the rows `sample` picks.
+    /// Also returns the number of rows they were inferred from, if any.
+    pub(crate) fn infer_schema(
+        &self,
+        sample: Sample,
+    ) -> Result<(Schema, Option<u64>), DatapumpError> {
+        let (path, headers) = match self {
+            SourceConfig::Csv { path, headers, .. } => (path.clone(), headers),
+            SourceConfig::CsvFiles { paths, headers, .. } => {
+                (files::expand(paths)?.remove(0), headers)
+            }
+            SourceConfig::FixedWidth { .. } => {
+                let layout = self.fixed_width_layout()?;
+                let schema = Schema {
+                    dialect: Some(layout.dialect.clone()),
+                    ..Schema::from_columns(&layout.schema())
+                };
+                return Ok((schema, None));
+            }
+        };
+        let dialect = self.dialect(&path, None)?;
+        let mut schema = csv_schema::get_csv_schema(&path, dialect, *headers, sample)?;
+        if let SourceConfig::CsvFiles { .. } = self {
+            // Only the first file was sniffed; each is sniffed for itself
+            schema.schema.dialect = None;
+        }
+        Ok((schema.schema, Some(schema.rows_sampled)))
+    }
+
+    /// Columns the source adds after those read from the file.
+    pub(crate) fn added_columns(&self) -> Vec<(String, String)> {
+        match self {
+            SourceConfig::CsvFiles {
+                source_file_column: true,
+                ..
+            } => vec![(SOURCE_FILE_COLUMN.to_string(), "text".to_string())],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Where a migration writes its records to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    Sqlite { path: String },
+    Postgres { connection_string: String },
+}
+
+impl SinkConfig {
+    async fn open(&self, table_name: &str, job_id: &str) -> Result<Box<dyn Sink>, DatapumpError> {
+        match self {
+            SinkConfig::Sqlite { path } => {
+                let (path, table_name, job_id) =
+                    (path.clone(), table_name.to_string(), job_id.to_string());
+                let sink =
+                    pipeline::blocking(move || SqliteSink::open(&path, &table_name, &job_id))
+                        .await?;
+                Ok(Box::new(sink))
+            }
+            SinkConfig::Postgres { connection_string } => Ok(Box::new(
+                PostgresSink::open(connection_string, table_name, job_id).await?,
+            )),
+        }
+    }
+}
+
+/// What to do when the target table already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadMode {
+    /// Fail if the table already exists.
+    Create,
+    /// Create the table if needed and add rows to it.
+    #[default]
+    Append,
+    /// Create the table if needed and delete its existing rows first.
+    Truncate,
+    /// Drop the table if it exists and create it from the schema.
+    Replace,
+}
+
+/// Register a job and run a migration, reporting progress to `progress`.
+///
+/// The job is checkpointed as it goes; if it fails or the process dies, it
+/// can be continued with `resume_migration`.
+pub async fn run_migration(
+    progress: &dyn ProgressSink,
+    spec: &JobSpec,
+    job_id: Option<String>,
+) -> Result<usize, DatapumpError> {
+    let job = jobs::registry().start(job_id)?;
+    let mut tracker = Tracker::new(progress, &job);
+    let result = start_job(&mut tracker, spec).await;
+    finish(&mut tracker, &result);
+    result
+}
+
+/// A member of an archive loaded by `import_archive`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemberImport {
+    pub member: String,
+    pub table_name: String,
+    pub rows: usize,
+}
+
+/// Load members of the zip archive that `source` points at into a table
+/// each, named as `archive::list_entries` names them. `members` picks which;
+/// when empty, every member in a supported format is loaded. The rest of
+/// `source`, such as the encoding, and `ragged` apply to every member.
+///
+/// Members are streamed out of the archive one after another, each as a job
+/// of its own; the first failure stops the import.
+pub async fn import_archive(
+    progress: &dyn ProgressSink,
+    source: &SourceConfig,
+    destination: &SinkConfig,
+    members: &[String],
+    mode: LoadMode,
+    batch_size: usize,
+    ragged: &RaggedRowPolicy,
+) -> Result<Vec<MemberImport>, DatapumpError> {
+    let SourceConfig::Csv {
+        path: archive_path,
+        dialect,
+        headers,
+    } = source
+    else {
+        return Err(DatapumpError::invalid(
+            "Only a single zip archive can be imported file by file",
+        ));
+    };
+    let all = {
+        let archive_path = archive_path.clone();
+        pipeline::blocking(move || archive::members(&archive_path)).await?
+    };
+    let tables = archive::table_names(&all);
+
+    let chosen: Vec<(String, String)> = if members.is_empty() {
+        all.into_iter()
+            .zip(tables)
+            .filter(|(member, _)| archive::is_delimited(member))
+            .collect()
+    } else {
+        members
+            .iter()
+            .map(|member| {
+                all.iter()
+                    .position(|name| name == member)
+                    .map(|i| (member.clone(), tables[i].clone()))
+                    .ok_or_else(|| {
+                        DatapumpError::invalid(format!(
+                            "No file named {} in {}",
+                            member, archive_path
+                        ))
+                    })
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut imported = Vec::with_capacity(chosen.len());
+    for (member, table_name) in chosen {
+        let source = SourceConfig::Csv {
+            path: archive::member_path(archive_path, &member),
+            dialect: dialect.clone(),
+            headers: *headers,
+        };
+        let mut spec = JobSpec::new(source, destination.clone(), table_name.clone());
+        spec.mode = mode;
+        spec.batch_size = batch_size;
+        spec.ragged = ragged.clone();
+        let rows = run_migration(progress, &spec, None).await?;
+        imported.push(MemberImport {
+            member,
+            table_name,
+            rows,
+        });
+    }
+    Ok(imported)
+}
+
+/// Continue an interrupted migration from its last checkpoint, without
+/// loading any committed row twice.
+pub async fn resume_migration(
+    progress: &dyn ProgressSink,
+    job_id: &str,
+) -> Result<usize, DatapumpError> {
+    let job = jobs::registry().start(Some(job_id.to_string()))?;
+    let mut tracker = Tracker::new(progress, &job);
+    let result = resume_job(&mut tracker).await;
+    finish(&mut tracker, &result);
+    result
+}
+
+fn finish(tracker: &mut Tracker<'_>, result: &Result<usize, DatapumpError>) {
+    tracker.finish(result);
+    tracker.job().finish(result);
+    // A failed or cancelled job keeps its checkpoint so it can be resumed
+    if result.is_ok() {
+        Checkpoint::remove(tracker.job().id());
+    }
+}
+
+async fn start_job(tracker: &mut Tracker<'_>, spec: &JobSpec) -> Result<usize, DatapumpError> {
+    spec.validate()?;
+
+    // Schema inference reads the file, so keep it off the async runtime
+    tracker.stage(Stage::Sniffing, None);
+    let sniff_spec = spec.clone();
+    let (resolved, rows_sampled) = pipeline::blocking(move || sniff_spec.resolved()).await?;
+    if let Some(rows) = rows_sampled {
+        let message = format!("Inferred column types from {} rows ({})", rows, spec.sample);
+        tracker.stage(Stage::Sniffing, Some(message));
+    }
+
+    let checkpoint = Checkpoint::new(tracker.job().id(), resolved);
+    run_job(tracker, checkpoint, false).await
+}
+
+async fn resume_job(tracker: &mut Tracker<'_>) -> Result<usize, DatapumpError> {
+    tracker.stage(Stage::Sniffing, Some("Loading checkpoint".to_string()));
+    let job_id = tracker.job().id().to_string();
+    let checkpoint = pipeline::blocking(move || Checkpoint::load(&job_id)).await?;
+    checkpoint.spec.validate()?;
+    run_job(tracker, checkpoint, true).await
+}
+
+/// Run the job described by `checkpoint`. When resuming, the destination's
+/// own record of what it committed decides where reading continues.
+async fn run_job(
+    tracker: &mut Tracker<'_>,
+    checkpoint: Checkpoint,
+    resume: bool,
+) -> Result<usize, DatapumpError> {
+    let spec = checkpoint.spec.clone();
+    let (mut source, mut checkpoint) = pipeline::blocking(move || {
+        let mut checkpoint = checkpoint;
+        checkpoint.save()?;
+
+        let columns = checkpoint.spec.resolve_columns()?;
+        let spec = &checkpoint.spec;
+        let dialect = spec
+            .schema
+            .as_ref()
+            .and_then(|schema| schema.dialect.as_ref());
+        let source = spec.source.open(columns, dialect, &spec.ragged)?;
+        Ok((source, checkpoint))
+    })
+    .await?;
+
+    tracker.stage(Stage::Connecting, None);
+    let mut sink = spec
+        .destination
+        .open(&spec.table_name, &checkpoint.job_id)
+        .await?;
+
+    let mut mode = spec.mode;
+    checkpoint.position = None;
+    checkpoint.rows_committed = 0;
+    if resume {
+        if let Some((position, rows_committed)) = sink.committed_checkpoint().await? {
+            checkpoint.position = Some(position);
+            checkpoint.rows_committed = rows_committed;
+            source = pipeline::blocking(move || {
+                source.seek(position)?;
+                Ok(source)
+            })
+            .await?;
+            // Committed rows must be kept, whatever the spec says
+            mode = LoadMode::Append;
+        }
+    }
+
+    pipeline::run(
+        tracker,
+        source,
+        sink.as_mut(),
+        mode,
+        spec.batch_size,
+        &mut checkpoint,
+    )
+    .await
+}
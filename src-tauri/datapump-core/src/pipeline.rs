@@ -0,0 +1,263 @@
+// src/pipeline.rs
+
+use crate::checkpoint::{Checkpoint, SourcePosition};
+use crate::error::DatapumpError;
+use crate::migrate::LoadMode;
+use crate::progress::{Stage, Tracker};
+use crate::ragged::RaggedRowCounts;
+use crate::sink::Sink;
+use crate::source::{self, RawRow, Record, Source};
+use crate::types::ColumnType;
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Batches that may be waiting between two stages. Together with the batch
+/// size this bounds how much of the source is held in memory at once.
+const QUEUE_DEPTH: usize = 4;
+
+/// Upper bound on parser workers; beyond this the writer is the bottleneck.
+const MAX_PARSERS: usize = 4;
+
+/// How often a paused job pings the destination to keep its connection open.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(60);
+
+type Parsed = Result<Vec<Record>, DatapumpError>;
+
+/// A batch of raw rows on its way to a parser, with the channel its typed
+/// records are returned on.
+type ParseJob = (Vec<RawRow>, oneshot::Sender<Parsed>);
+
+/// A parsed batch as the writer awaits it, in source order, together with
+/// where in the source the reader was after reading it.
+type Pending = (oneshot::Receiver<Parsed>, SourcePosition);
+
+/// Move every record from `source` into `sink`, `batch_size` records at a time.
+///
+/// This is the one loop shared by all migrations: it creates the target,
+/// honours the job's cancellation and pause tokens between batches, reports progress
+/// after each batch, commits or aborts the sink and checks the destination
+/// accepted every row. Returns the number of rows written; ragged rows the
+/// source fitted or rejected are counted in the final report.
+///
+/// Every batch is committed together with the source position after it, and
+/// `checkpoint` is updated to match, so an interrupted job can continue from
+/// there. A checkpoint that already has rows committed means the source has
+/// been positioned past them and counting continues from there.
+///
+/// Reading, parsing and writing run concurrently: a reader thread pulls raw
+/// rows from the source, a few parser threads turn them into typed records
+/// and the sink writes them from this task. Bounded queues between the
+/// stages keep a fast reader from running ahead of a slow destination.
+pub async fn run(
+    tracker: &mut Tracker<'_>,
+    source: Box<dyn Source>,
+    sink: &mut dyn Sink,
+    mode: LoadMode,
+    batch_size: usize,
+    checkpoint: &mut Checkpoint,
+) -> Result<usize, DatapumpError> {
+    tracker.set_total_bytes(source.total_bytes());
+
+    // 1. Create or ensure the target exists
+    tracker.stage(Stage::CreatingTable, None);
+    let columns = source.schema().to_vec();
+    let constraints = &checkpoint.spec.constraints;
+    constraints.validate(&columns)?;
+    sink.create(&columns, constraints, mode).await?;
+
+    // 2. Copy records, one batch at a time
+    let resumed_bytes = checkpoint.position.map_or(0, |position| position.byte);
+    tracker.resume_from(checkpoint.rows_committed, resumed_bytes);
+    tracker.stage(Stage::Loading, None);
+    let resumed_rows = checkpoint.rows_committed;
+    let (processed_rows, ragged) =
+        match load(tracker, source, sink, columns, batch_size, checkpoint).await {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                let _ = sink.abort().await;
+                return Err(e);
+            }
+        };
+
+    // 3. Commit
+    tracker.stage(Stage::Finalizing, None);
+    let loaded_rows = sink.commit().await?;
+
+    // 4. Make sure the destination got every row we sent
+    tracker.stage(Stage::Verifying, None);
+    let expected_rows = processed_rows - resumed_rows;
+    if loaded_rows != expected_rows as u64 {
+        return Err(DatapumpError::Database {
+            message: format!(
+                "Destination reported {} rows loaded, expected {}",
+                loaded_rows, expected_rows
+            ),
+            sqlstate: None,
+            detail: None,
+        });
+    }
+
+    let mut message = format!("Successfully copied {} rows", processed_rows);
+    if !ragged.is_empty() {
+        message.push_str(&format!(" ({})", ragged));
+    }
+    tracker.set_ragged_rows(ragged);
+    tracker.stage(Stage::Done, Some(message));
+    Ok(processed_rows)
+}
+
+/// Run the reader and parsers and write their batches, in source order, as
+/// they become ready. Returning early drops the queues, which stops the
+/// other stages. Returns the rows processed and the ragged rows counted.
+async fn load(
+    tracker: &mut Tracker<'_>,
+    source: Box<dyn Source>,
+    sink: &mut dyn Sink,
+    columns: Vec<(String, String)>,
+    batch_size: usize,
+    checkpoint: &mut Checkpoint,
+) -> Result<(usize, RaggedRowCounts), DatapumpError> {
+    let (pending_tx, mut pending_rx) = mpsc::channel(QUEUE_DEPTH);
+    let (parse_tx, parse_rx) = std_mpsc::sync_channel(QUEUE_DEPTH);
+
+    let parse_rx = Arc::new(Mutex::new(parse_rx));
+    let types: Arc<[ColumnType]> = columns
+        .iter()
+        .map(|(_, typ)| ColumnType::parse(typ))
+        .collect();
+    for _ in 0..parser_count() {
+        let parse_rx = Arc::clone(&parse_rx);
+        let types = Arc::clone(&types);
+        tokio::task::spawn_blocking(move || parse_batches(&parse_rx, &types));
+    }
+    let reader = tokio::task::spawn_blocking(move || {
+        read_batches(source, batch_size.max(1), &pending_tx, &parse_tx)
+    });
+
+    let mut processed_rows = checkpoint.rows_committed;
+    while let Some((parsed, position)) = pending_rx.recv().await {
+        if tracker.job().is_paused() {
+            wait_while_paused(tracker, sink).await?;
+        }
+
+        // Check for user cancellation
+        if tracker.job().is_cancelled() {
+            return Err(DatapumpError::Cancelled);
+        }
+
+        let records = parsed.await.map_err(|_| DatapumpError::Internal {
+            message: "Parser stopped unexpectedly".to_string(),
+        })??;
+        processed_rows += records.len();
+        sink.write_batch(records, position, processed_rows).await?;
+        tracker.advance(processed_rows, position.byte);
+
+        checkpoint.position = Some(position);
+        checkpoint.rows_committed = processed_rows;
+        let mut saved = checkpoint.clone();
+        blocking(move || saved.save()).await?;
+    }
+
+    // The queue also closes when the reader fails; only a clean end of input counts
+    let ragged = reader.await??;
+    Ok((processed_rows, ragged))
+}
+
+/// Hold the load between batches, where everything written so far is
+/// committed, until the job is resumed or cancelled.
+async fn wait_while_paused(
+    tracker: &mut Tracker<'_>,
+    sink: &mut dyn Sink,
+) -> Result<(), DatapumpError> {
+    tracker.stage(Stage::Paused, None);
+    while wait_for_wake(tracker).await {
+        sink.keepalive().await?;
+    }
+    tracker.stage(Stage::Loading, None);
+    Ok(())
+}
+
+/// Wait while the job is paused, for at most `KEEPALIVE_INTERVAL`. Returns
+/// whether it is still paused, in which case the destination should be
+/// pinged before waiting again.
+pub(crate) async fn wait_for_wake(tracker: &Tracker<'_>) -> bool {
+    while tracker.job().is_paused() && !tracker.job().is_cancelled() {
+        let woken = tokio::time::timeout(KEEPALIVE_INTERVAL, tracker.job().woken()).await;
+        if woken.is_err() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Reader stage: cut the source into batches, queue each for parsing and
+/// queue the slot its result will arrive in for the writer, in that order.
+/// At the end of the source, returns the ragged rows it counted, including
+/// any after the last batch.
+fn read_batches(
+    mut source: Box<dyn Source>,
+    batch_size: usize,
+    pending_tx: &mpsc::Sender<Pending>,
+    parse_tx: &std_mpsc::SyncSender<ParseJob>,
+) -> Result<RaggedRowCounts, DatapumpError> {
+    loop {
+        let mut rows = Vec::with_capacity(batch_size);
+        while rows.len() < batch_size {
+            match source.next_row()? {
+                Some(row) => rows.push(row),
+                None => break,
+            }
+        }
+        if rows.is_empty() {
+            return Ok(source.position().ragged);
+        }
+        let last = rows.len() < batch_size;
+
+        let (result_tx, result_rx) = oneshot::channel();
+        // Either send only fails once the writer has stopped, so just stop too
+        if pending_tx
+            .blocking_send((result_rx, source.position()))
+            .is_err()
+            || parse_tx.send((rows, result_tx)).is_err()
+        {
+            return Ok(RaggedRowCounts::default());
+        }
+        if last {
+            return Ok(source.position().ragged);
+        }
+    }
+}
+
+/// Parser stage: convert batches until the reader hangs up.
+fn parse_batches(parse_rx: &Mutex<std_mpsc::Receiver<ParseJob>>, types: &[ColumnType]) {
+    loop {
+        let next = parse_rx.lock().map(|rx| rx.recv());
+        let Ok(Ok((rows, result_tx))) = next else {
+            return;
+        };
+        let records = rows
+            .iter()
+            .map(|row| source::parse_row(row, types))
+            .collect();
+        // The writer may already have given up on this batch
+        let _ = result_tx.send(records);
+    }
+}
+
+/// Leave a core each for the reader and the writer.
+fn parser_count() -> usize {
+    std::thread::available_parallelism()
+        .map_or(1, |n| n.get().saturating_sub(2))
+        .clamp(1, MAX_PARSERS)
+}
+
+/// Run blocking work (file or SQLite I/O) on the blocking thread pool so it
+/// does not stall the async runtime.
+pub(crate) async fn blocking<T, F>(f: F) -> Result<T, DatapumpError>
+where
+    F: FnOnce() -> Result<T, DatapumpError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await?
+}
@@ -0,0 +1,560 @@
+use crate::checkpoint::{Checkpoint, SourcePosition};
+use crate::compression;
+use crate::csv_reader::create_csv_reader;
+use crate::dialect::Dialect;
+use crate::error::{Context, DatapumpError};
+use crate::headers::{self, HeaderPolicy};
+use crate::input::CsvInput;
+use crate::jobs;
+use crate::migrate::{SinkConfig, SourceConfig};
+use crate::pipeline;
+use crate::postgres_writer::{
+    self, finish_copy, start_copy, table_exists as table_exists_on, write_binary_header,
+};
+use crate::progress::{ProgressSink, Stage, Tracker};
+use crate::ragged::{RaggedRowCounts, RaggedRowPolicy, RowFitter};
+use crate::schema::Schema;
+use crate::sink::encode_position;
+use crate::spec::JobSpec;
+use crate::types::{self, ColumnType};
+use bytes::BytesMut;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use futures_util::SinkExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str;
+use tokio::sync::mpsc;
+use tokio_postgres::{Client, NoTls};
+
+/// Rows encoded into one COPY, committed together with the job's checkpoint.
+const BATCH_THRESHOLD: usize = 5000;
+
+/// Encoded batches that may be waiting for the connection.
+const QUEUE_DEPTH: usize = 4;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Field {
+    name: String,
+    field_type: String,
+}
+
+impl Field {
+    /// The column type, which must match the binary encoding in `FieldProcessor`.
+    fn to_postgres_type(&self) -> String {
+        match ColumnType::parse(&self.field_type) {
+            ColumnType::Integer(_) => "BIGINT".to_string(),
+            ColumnType::Real(_) => "DOUBLE PRECISION".to_string(),
+            ColumnType::Boolean => "BOOLEAN".to_string(),
+            ColumnType::Date(_) => "DATE".to_string(),
+            ColumnType::Time(_) => "TIME".to_string(),
+            ColumnType::Timestamp(_) => "TIMESTAMP".to_string(),
+            ColumnType::TimestampTz(_) => "TIMESTAMPTZ".to_string(),
+            ColumnType::Uuid => "UUID".to_string(),
+            ColumnType::Json => "JSONB".to_string(),
+            _ => "TEXT".to_string(),
+        }
+    }
+}
+
+fn parse_fields(fields: Vec<Value>) -> Result<Vec<Field>, DatapumpError> {
+    fields
+        .into_iter()
+        .map(|field| {
+            let obj = field
+                .as_object()
+                .ok_or_else(|| DatapumpError::invalid("Field is not an object"))?;
+            let name = obj
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| DatapumpError::invalid("Field name not found or not a string"))?
+                .to_string();
+            let field_type = obj
+                .get("type")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| DatapumpError::invalid("Field type not found or not a string"))?
+                .to_string();
+
+            Ok(Field { name, field_type })
+        })
+        .collect()
+}
+
+fn create_table_sql(table_name: &str, fields: &[Field]) -> String {
+    let columns = fields
+        .iter()
+        .map(|field| format!("\"{}\" {}", field.name, field.to_postgres_type()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "CREATE TABLE IF NOT EXISTS \"{}\" ({})",
+        table_name, columns
+    )
+}
+
+/// Check whether a table with the given name already exists.
+pub async fn table_exists(
+    connection_string: &str,
+    table_name: &str,
+) -> Result<bool, DatapumpError> {
+    let (client, connection) = tokio_postgres::connect(connection_string, NoTls)
+        .await
+        .context("Failed to connect to database")?;
+
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+
+    table_exists_on(&client, table_name).await
+}
+
+/// Binary COPY fast path: loads a delimited file with `COPY ... (FORMAT binary)`,
+/// read in `dialect`. Rows with more or fewer fields than the header are
+/// handled as `ragged` says.
+///
+/// Every batch is committed together with the job's checkpoint, so a load
+/// that fails part way can be continued with `resume_migration`, which
+/// loads the rest of the file through the generic PostgreSQL sink. A paused
+/// job waits between batches.
+#[allow(clippy::too_many_arguments)]
+pub async fn import_csv(
+    progress: &dyn ProgressSink,
+    connection_string: &str,
+    path_to_file: &str,
+    table_name: &str,
+    dialect: &Dialect,
+    fields: Vec<Value>,
+    ragged: &RaggedRowPolicy,
+    job_id: Option<String>,
+) -> Result<(), DatapumpError> {
+    let job = jobs::registry().start(job_id)?;
+    let mut tracker = Tracker::new(progress, &job);
+    let result = copy_csv_to_postgres(
+        &mut tracker,
+        connection_string,
+        path_to_file,
+        table_name,
+        dialect,
+        fields,
+        ragged,
+    )
+    .await;
+    tracker.finish(&result);
+    job.finish(&result);
+    // A failed or cancelled load keeps its checkpoint so it can be resumed
+    if result.is_ok() {
+        Checkpoint::remove(job.id());
+    }
+    result
+}
+
+/// Build the reader for the fast path. Quoted fields may contain delimiters,
+/// escaped quotes and line breaks; compressed files are decompressed and
+/// files that are not UTF-8 transcoded as they are read, and preamble,
+/// comment and footer lines left out.
+fn csv_reader(
+    path_to_file: &str,
+    dialect: &Dialect,
+    flexible: bool,
+) -> Result<csv::Reader<CsvInput>, DatapumpError> {
+    dialect.validate()?;
+    create_csv_reader(path_to_file, dialect, flexible)
+}
+
+async fn copy_csv_to_postgres(
+    tracker: &mut Tracker<'_>,
+    connection_string: &str,
+    path_to_file: &str,
+    table_name: &str,
+    dialect: &Dialect,
+    fields: Vec<Value>,
+    ragged: &RaggedRowPolicy,
+) -> Result<(), DatapumpError> {
+    ragged.validate()?;
+
+    // Parse fields into our internal representation
+    let mut parsed_fields = parse_fields(fields)?;
+
+    // Names come from the file's header row; make them safe to create
+    let names = headers::normalize(
+        parsed_fields.iter().map(|f| f.name.as_str()),
+        HeaderPolicy::default(),
+    );
+    for (field, column) in parsed_fields.iter_mut().zip(names) {
+        field.name = column.name;
+    }
+
+    // Generate CREATE TABLE SQL
+    let create_table_sql = create_table_sql(table_name, &parsed_fields);
+    let columns: Vec<(String, String)> = parsed_fields
+        .iter()
+        .map(|f| (f.name.clone(), f.field_type.clone()))
+        .collect();
+
+    // What a resumed job needs to read the rest of the file the same way
+    let mut spec = JobSpec::new(
+        SourceConfig::Csv {
+            path: path_to_file.to_string(),
+            dialect: dialect.options(),
+            headers: HeaderPolicy::default(),
+        },
+        SinkConfig::Postgres {
+            connection_string: connection_string.to_string(),
+        },
+        table_name,
+    );
+    spec.schema = Some(Schema {
+        dialect: Some(dialect.clone()),
+        ..Schema::from_columns(&columns)
+    });
+    spec.ragged = ragged.clone();
+    let mut checkpoint = Checkpoint::new(tracker.job().id(), spec);
+
+    // Open the file and read its header before touching the database
+    let (path, dialect, flexible) = (
+        path_to_file.to_string(),
+        dialect.clone(),
+        ragged.is_flexible(),
+    );
+    let mut reader = pipeline::blocking(move || {
+        let mut reader = csv_reader(&path, &dialect, flexible)?;
+        reader.byte_headers()?;
+        Ok(reader)
+    })
+    .await?;
+    let total_bytes = compression::input_size(path_to_file);
+    tracker.set_total_bytes(total_bytes);
+
+    // Emit initial progress event
+    tracker.stage(
+        Stage::Connecting,
+        Some("Connecting to PostgreSQL".to_string()),
+    );
+
+    // Connect to PostgreSQL
+    let (client, connection) = tokio_postgres::connect(connection_string, NoTls).await?;
+
+    // Spawn connection task
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Connection error: {}", e);
+        }
+    });
+
+    // Create table if it doesn't exist
+    tracker.stage(Stage::CreatingTable, None);
+    client
+        .execute(&create_table_sql, &[])
+        .await
+        .context("Failed to create table")?;
+
+    // Tokenizing and encoding run on a blocking thread, bounded by the queue
+    let field_processors: Vec<FieldProcessor> = parsed_fields
+        .iter()
+        .map(|field| FieldProcessor {
+            name: field.name.clone(),
+            column_type: ColumnType::parse(&field.field_type),
+        })
+        .collect();
+    let mut fitter = RowFitter::new(ragged, path_to_file, field_processors.len());
+    let (batch_tx, mut batch_rx) = mpsc::channel(QUEUE_DEPTH);
+    let encoder = tokio::task::spawn_blocking(move || {
+        encode_batches(&mut reader, &field_processors, &mut fitter, &batch_tx)
+    });
+
+    let mut processed_rows = 0;
+    let mut loaded_rows = 0;
+    tracker.stage(Stage::Loading, None);
+
+    while let Some(batch) = batch_rx.recv().await {
+        // Paused between batches, where everything copied so far is committed
+        if tracker.job().is_paused() {
+            tracker.stage(Stage::Paused, None);
+            while pipeline::wait_for_wake(tracker).await {
+                client
+                    .batch_execute("SELECT 1")
+                    .await
+                    .context("Lost connection while paused")?;
+            }
+            tracker.stage(Stage::Loading, None);
+        }
+
+        if tracker.job().is_cancelled() {
+            return Err(DatapumpError::Cancelled);
+        }
+
+        let EncodedBatch {
+            data,
+            rows,
+            position,
+        } = batch;
+        processed_rows += rows;
+        loaded_rows += copy_batch(
+            &client,
+            table_name,
+            &columns,
+            tracker.job().id(),
+            data,
+            position,
+            processed_rows,
+        )
+        .await?;
+        tracker.advance(processed_rows, position.byte);
+
+        checkpoint.position = Some(position);
+        checkpoint.rows_committed = processed_rows;
+        let mut saved = checkpoint.clone();
+        pipeline::blocking(move || saved.save()).await?;
+    }
+
+    // The queue also closes when the file turns out to be malformed
+    let ragged_rows = encoder.await??;
+
+    // Everything is committed, so there is nothing left to resume
+    tracker.stage(Stage::Finalizing, None);
+    client
+        .batch_execute("BEGIN")
+        .await
+        .context("Failed to start transaction")?;
+    postgres_writer::delete_checkpoint(&client, tracker.job().id()).await?;
+    client
+        .batch_execute("COMMIT")
+        .await
+        .context("Failed to commit")?;
+
+    tracker.stage(Stage::Verifying, None);
+    if loaded_rows != processed_rows as u64 {
+        return Err(DatapumpError::Database {
+            message: format!(
+                "PostgreSQL reported {} rows copied, expected {}",
+                loaded_rows, processed_rows
+            ),
+            sqlstate: None,
+            detail: None,
+        });
+    }
+
+    let mut message = format!("Successfully imported {} rows", processed_rows);
+    if !ragged_rows.is_empty() {
+        message.push_str(&format!(" ({})", ragged_rows));
+    }
+    tracker.set_ragged_rows(ragged_rows);
+    tracker.stage(Stage::Done, Some(message));
+
+    Ok(())
+}
+
+/// Copy one batch in its own transaction, together with the job's checkpoint
+/// of `rows_committed` rows up to `position`. Returns the rows copied.
+async fn copy_batch(
+    client: &Client,
+    table_name: &str,
+    columns: &[(String, String)],
+    job_id: &str,
+    data: BytesMut,
+    position: SourcePosition,
+    rows_committed: usize,
+) -> Result<u64, DatapumpError> {
+    // An error leaves the transaction open; the connection is dropped with it
+    client
+        .batch_execute("BEGIN")
+        .await
+        .context("Failed to start transaction")?;
+    let mut writer = start_copy(client, table_name, columns).await?;
+    writer.send(data).await?;
+    let copied = finish_copy(writer).await?;
+
+    postgres_writer::create_checkpoint_table(client).await?;
+    postgres_writer::save_checkpoint(
+        client,
+        job_id,
+        &encode_position(&position),
+        rows_committed as i64,
+    )
+    .await?;
+    client
+        .batch_execute("COMMIT")
+        .await
+        .context("Failed to commit batch")?;
+    Ok(copied)
+}
+
+/// A complete binary COPY of a batch of rows, ready to send.
+struct EncodedBatch {
+    data: BytesMut,
+    rows: usize,
+    /// Where in the file the rows after this batch start.
+    position: SourcePosition,
+}
+
+/// Tokenize the file and encode it as binary COPY tuples, `BATCH_THRESHOLD`
+/// rows per batch, each batch with the COPY header and trailer. Ragged rows
+/// are fitted to the columns or rejected by `fitter`, whose counts are
+/// returned. Stops early, without error, if the receiving side has gone away.
+fn encode_batches(
+    reader: &mut csv::Reader<CsvInput>,
+    field_processors: &[FieldProcessor],
+    fitter: &mut RowFitter,
+    batch_tx: &mpsc::Sender<EncodedBatch>,
+) -> Result<RaggedRowCounts, DatapumpError> {
+    // Fields are borrowed straight from the record buffer, which is reused
+    let mut record = csv::ByteRecord::new();
+    let mut batch_buffer = BytesMut::with_capacity(2 * 1024 * 1024);
+    let mut batch_rows = 0;
+
+    loop {
+        if batch_buffer.is_empty() {
+            write_binary_header(&mut batch_buffer);
+        }
+        let more = reader.read_byte_record(&mut record).map_err(|e| {
+            DatapumpError::from(e).map_line(|line| reader.get_ref().file_line(line))
+        })?;
+        let line = record
+            .position()
+            .map(|pos| reader.get_ref().file_line(pos.line()));
+        if more && fitter.fit(&mut record, line)? {
+            batch_buffer.extend_from_slice(&(field_processors.len() as i16).to_be_bytes());
+            for (processor, raw) in field_processors.iter().zip(record.iter()) {
+                processor.process_value(raw, &mut batch_buffer, line)?;
+            }
+            batch_rows += 1;
+        }
+
+        if batch_rows >= BATCH_THRESHOLD || (!more && batch_rows > 0) {
+            batch_buffer.extend_from_slice(&(-1i16).to_be_bytes());
+            let position = reader.position();
+            let batch = EncodedBatch {
+                data: batch_buffer.split(),
+                rows: batch_rows,
+                // The same position the generic CSV source resumes from
+                position: SourcePosition {
+                    byte: reader.get_ref().bytes_read(position.byte()),
+                    line: position.line(),
+                    record: position.record(),
+                    file: 0,
+                    ragged: fitter.counts,
+                },
+            };
+            if batch_tx.blocking_send(batch).is_err() {
+                return Ok(fitter.counts);
+            }
+            batch_rows = 0;
+        }
+        if !more {
+            return Ok(fitter.counts);
+        }
+    }
+}
+
+struct FieldProcessor {
+    name: String,
+    column_type: ColumnType,
+}
+
+impl FieldProcessor {
+    /// Append one field of a binary COPY tuple: its length, then the value in
+    /// the binary format of the column type. Empty fields become NULL.
+    fn process_value(
+        &self,
+        raw: &[u8],
+        binary_record: &mut BytesMut,
+        line: Option<u64>,
+    ) -> Result<(), DatapumpError> {
+        let value = str::from_utf8(raw).map_err(|_| DatapumpError::Parse {
+            message: "Invalid UTF-8".to_string(),
+            line,
+            column: Some(self.name.clone()),
+            value: Some(String::from_utf8_lossy(raw).into_owned()),
+        })?;
+        let invalid = |message: &str| DatapumpError::TypeCoercion {
+            message: message.to_string(),
+            line,
+            column: Some(self.name.clone()),
+            value: Some(value.to_string()),
+            sqlstate: None,
+        };
+
+        let trimmed = value.trim();
+        let is_text = matches!(self.column_type, ColumnType::Text | ColumnType::Other(_));
+        if value.is_empty() || (trimmed.is_empty() && !is_text) {
+            binary_record.extend_from_slice(&(-1i32).to_be_bytes());
+            return Ok(());
+        }
+
+        // Dates and times count from the PostgreSQL epoch
+        let epoch = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        let micros_since_epoch = |timestamp: NaiveDateTime| {
+            timestamp
+                .signed_duration_since(epoch.and_hms_opt(0, 0, 0).unwrap())
+                .num_microseconds()
+                .ok_or_else(|| invalid("Timestamp out of range"))
+        };
+        match &self.column_type {
+            ColumnType::Integer(format) => {
+                let value = types::parse_integer(trimmed, format.as_deref())
+                    .ok_or_else(|| invalid("Invalid integer"))?;
+                binary_record.extend_from_slice(&8i32.to_be_bytes());
+                binary_record.extend_from_slice(&value.to_be_bytes());
+            }
+            ColumnType::Real(format) => {
+                let value = types::parse_real(trimmed, format.as_deref())
+                    .ok_or_else(|| invalid("Invalid number"))?;
+                binary_record.extend_from_slice(&8i32.to_be_bytes());
+                binary_record.extend_from_slice(&value.to_be_bytes());
+            }
+            ColumnType::Boolean => {
+                let value = types::parse_bool(trimmed).ok_or_else(|| invalid("Invalid boolean"))?;
+                binary_record.extend_from_slice(&1i32.to_be_bytes());
+                binary_record.extend_from_slice(&[value as u8]);
+            }
+            ColumnType::Date(format) => {
+                let date = types::parse_date(trimmed, format.as_deref())
+                    .ok_or_else(|| invalid("Invalid date"))?;
+                let days = date.signed_duration_since(epoch).num_days() as i32;
+                binary_record.extend_from_slice(&4i32.to_be_bytes());
+                binary_record.extend_from_slice(&days.to_be_bytes());
+            }
+            ColumnType::Time(format) => {
+                let time = types::parse_time(trimmed, format.as_deref())
+                    .ok_or_else(|| invalid("Invalid time"))?;
+                let micros = time
+                    .signed_duration_since(NaiveTime::MIN)
+                    .num_microseconds()
+                    .unwrap_or_default();
+                binary_record.extend_from_slice(&8i32.to_be_bytes());
+                binary_record.extend_from_slice(&micros.to_be_bytes());
+            }
+            ColumnType::Timestamp(format) => {
+                let timestamp = types::parse_timestamp(trimmed, format.as_deref())
+                    .ok_or_else(|| invalid("Invalid timestamp"))?;
+                binary_record.extend_from_slice(&8i32.to_be_bytes());
+                binary_record.extend_from_slice(&micros_since_epoch(timestamp)?.to_be_bytes());
+            }
+            ColumnType::TimestampTz(format) => {
+                let timestamp = types::parse_timestamptz(trimmed, format.as_deref())
+                    .ok_or_else(|| invalid("Invalid timestamp"))?;
+                binary_record.extend_from_slice(&8i32.to_be_bytes());
+                binary_record
+                    .extend_from_slice(&micros_since_epoch(timestamp.naive_utc())?.to_be_bytes());
+            }
+            ColumnType::Uuid => {
+                let uuid = uuid::Uuid::try_parse(trimmed).map_err(|_| invalid("Invalid UUID"))?;
+                binary_record.extend_from_slice(&16i32.to_be_bytes());
+                binary_record.extend_from_slice(uuid.as_bytes());
+            }
+            ColumnType::Json => {
+                // jsonb is sent as a version byte followed by the JSON text
+                binary_record.extend_from_slice(&(raw.len() as i32 + 1).to_be_bytes());
+                binary_record.extend_from_slice(&[1]);
+                binary_record.extend_from_slice(raw);
+            }
+            ColumnType::Text | ColumnType::Other(_) => {
+                binary_record.extend_from_slice(&(raw.len() as i32).to_be_bytes());
+                binary_record.extend_from_slice(raw);
+            }
+        }
+        Ok(())
+    }
+}
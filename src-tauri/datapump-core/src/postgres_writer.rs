@@ -0,0 +1,265 @@
+use crate::checkpoint::CHECKPOINT_TABLE;
+use crate::constraints::Constraints;
+use crate::error::{Context, DatapumpError};
+use crate::source::Value;
+use crate::types::ColumnType;
+use bytes::BytesMut;
+use std::pin::Pin;
+use tokio_postgres::{Client, CopyInSink, NoTls};
+
+/// The sink end of a `COPY ... FROM STDIN`.
+pub type CopyWriter = Pin<Box<CopyInSink<BytesMut>>>;
+
+/// Open a PostgreSQL connection
+pub async fn open_connection(connection_string: &str) -> Result<Client, DatapumpError> {
+    let (client, connection) = tokio_postgres::connect(connection_string, NoTls)
+        .await
+        .context("Failed to connect to PostgreSQL")?;
+
+    // Spawn connection task
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Connection error: {}", e);
+        }
+    });
+
+    Ok(client)
+}
+
+/// Create table if not exists, given a table name and columns
+pub async fn create_table(
+    client: &Client,
+    table_name: &str,
+    columns: &[(String, String)],
+    constraints: &Constraints,
+) -> Result<(), DatapumpError> {
+    let create_table_sql = format!(
+        "CREATE TABLE IF NOT EXISTS \"{}\" ({})",
+        table_name,
+        constraints.table_definition(columns, ColumnType::postgres_type)
+    );
+
+    client
+        .execute(&create_table_sql, &[])
+        .await
+        .context("Failed to create table")?;
+
+    Ok(())
+}
+
+/// Check whether a table with the given name exists.
+pub async fn table_exists(client: &Client, table_name: &str) -> Result<bool, DatapumpError> {
+    let row = client
+        .query_one(
+            "SELECT EXISTS (SELECT 1 FROM information_schema.tables WHERE table_name = $1)",
+            &[&table_name],
+        )
+        .await
+        .context("Failed to check if table exists")?;
+
+    Ok(row.get(0))
+}
+
+/// Create the table that records each job's committed checkpoint.
+pub async fn create_checkpoint_table(client: &Client) -> Result<(), DatapumpError> {
+    client
+        .execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS \"{}\" (job_id TEXT PRIMARY KEY, position TEXT NOT NULL, rows_committed BIGINT NOT NULL)",
+                CHECKPOINT_TABLE
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to create checkpoint table")?;
+    Ok(())
+}
+
+/// Record a job's checkpoint; meant to run inside the batch's transaction.
+pub async fn save_checkpoint(
+    client: &Client,
+    job_id: &str,
+    position: &str,
+    rows_committed: i64,
+) -> Result<(), DatapumpError> {
+    client
+        .execute(
+            &format!(
+                "INSERT INTO \"{}\" (job_id, position, rows_committed) VALUES ($1, $2, $3) \
+                 ON CONFLICT (job_id) DO UPDATE SET position = EXCLUDED.position, rows_committed = EXCLUDED.rows_committed",
+                CHECKPOINT_TABLE
+            ),
+            &[&job_id, &position, &rows_committed],
+        )
+        .await
+        .context("Failed to save checkpoint")?;
+    Ok(())
+}
+
+/// The committed position and row count of a job, if it has a checkpoint.
+pub async fn load_checkpoint(
+    client: &Client,
+    job_id: &str,
+) -> Result<Option<(String, i64)>, DatapumpError> {
+    if !table_exists(client, CHECKPOINT_TABLE).await? {
+        return Ok(None);
+    }
+    let row = client
+        .query_opt(
+            &format!(
+                "SELECT position, rows_committed FROM \"{}\" WHERE job_id = $1",
+                CHECKPOINT_TABLE
+            ),
+            &[&job_id],
+        )
+        .await
+        .context("Failed to load checkpoint")?;
+    Ok(row.map(|row| (row.get(0), row.get(1))))
+}
+
+/// Remove a job's checkpoint once it has completed, and the checkpoint table
+/// once no job has one; meant to run inside a transaction.
+pub async fn delete_checkpoint(client: &Client, job_id: &str) -> Result<(), DatapumpError> {
+    if !table_exists(client, CHECKPOINT_TABLE).await? {
+        return Ok(());
+    }
+    // Locked first, so no other job's checkpoint is written in between
+    client
+        .batch_execute(&format!(
+            "LOCK TABLE \"{}\" IN ACCESS EXCLUSIVE MODE",
+            CHECKPOINT_TABLE
+        ))
+        .await
+        .context("Failed to lock checkpoint table")?;
+    client
+        .execute(
+            &format!("DELETE FROM \"{}\" WHERE job_id = $1", CHECKPOINT_TABLE),
+            &[&job_id],
+        )
+        .await
+        .context("Failed to delete checkpoint")?;
+    let row = client
+        .query_one(
+            &format!("SELECT EXISTS (SELECT 1 FROM \"{}\")", CHECKPOINT_TABLE),
+            &[],
+        )
+        .await
+        .context("Failed to delete checkpoint")?;
+    if !row.get::<_, bool>(0) {
+        drop_table(client, CHECKPOINT_TABLE).await?;
+    }
+    Ok(())
+}
+
+/// Drop the table if it exists.
+pub async fn drop_table(client: &Client, table_name: &str) -> Result<(), DatapumpError> {
+    client
+        .execute(&format!("DROP TABLE IF EXISTS \"{}\"", table_name), &[])
+        .await
+        .context("Failed to drop table")?;
+    Ok(())
+}
+
+/// Delete every row from the table.
+pub async fn truncate_table(client: &Client, table_name: &str) -> Result<(), DatapumpError> {
+    client
+        .execute(&format!("TRUNCATE TABLE \"{}\"", table_name), &[])
+        .await
+        .context("Failed to truncate table")?;
+    Ok(())
+}
+
+/// Start a binary-format COPY operation for bulk loading
+pub async fn start_copy(
+    client: &Client,
+    table_name: &str,
+    columns: &[(String, String)],
+) -> Result<CopyWriter, DatapumpError> {
+    let column_names = columns
+        .iter()
+        .map(|(name, _)| format!("\"{}\"", name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let copy_sql = format!(
+        "COPY \"{}\" ({}) FROM STDIN WITH (FORMAT binary)",
+        table_name, column_names
+    );
+
+    let writer = client
+        .copy_in(&copy_sql)
+        .await
+        .context("Failed to start COPY operation")?;
+
+    Ok(Box::pin(writer))
+}
+
+/// Start a text-format COPY operation (tab-delimited, `\N` for NULL).
+pub async fn start_text_copy(
+    client: &Client,
+    table_name: &str,
+    columns: &[(String, String)],
+) -> Result<CopyWriter, DatapumpError> {
+    let column_names = columns
+        .iter()
+        .map(|(name, _)| format!("\"{}\"", name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let copy_sql = format!("COPY \"{}\" ({}) FROM STDIN", table_name, column_names);
+
+    let writer = client
+        .copy_in(&copy_sql)
+        .await
+        .context("Failed to start COPY operation")?;
+
+    Ok(Box::pin(writer))
+}
+
+/// Append a record to `buf` as one line of COPY text format.
+pub fn encode_text_row(buf: &mut BytesMut, values: &[Value]) {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            buf.extend_from_slice(b"\t");
+        }
+        match value {
+            Value::Null => buf.extend_from_slice(b"\\N"),
+            Value::Integer(n) => buf.extend_from_slice(n.to_string().as_bytes()),
+            Value::Real(f) => buf.extend_from_slice(f.to_string().as_bytes()),
+            Value::Text(s) => {
+                for b in s.bytes() {
+                    match b {
+                        b'\\' => buf.extend_from_slice(b"\\\\"),
+                        b'\t' => buf.extend_from_slice(b"\\t"),
+                        b'\n' => buf.extend_from_slice(b"\\n"),
+                        b'\r' => buf.extend_from_slice(b"\\r"),
+                        _ => buf.extend_from_slice(&[b]),
+                    }
+                }
+            }
+            // Booleans, dates and times have nothing to escape
+            other => buf.extend_from_slice(other.to_string().as_bytes()),
+        }
+    }
+    buf.extend_from_slice(b"\n");
+}
+
+/// Finish a COPY operation
+pub async fn finish_copy(mut writer: CopyWriter) -> Result<u64, DatapumpError> {
+    let rows = writer
+        .as_mut()
+        .finish()
+        .await
+        .context("Failed to finish COPY operation")?;
+    Ok(rows) // Return number of rows copied
+}
+
+/// Write a header for PostgreSQL binary COPY format
+pub fn write_binary_header(buf: &mut BytesMut) {
+    // PGCOPY\n\377\r\n\0
+    buf.extend_from_slice(b"PGCOPY\n\xff\r\n\0");
+    // Flags field (0 for no OIDs)
+    buf.extend_from_slice(&[0, 0, 0, 0]);
+    // Header extension length (0)
+    buf.extend_from_slice(&[0, 0, 0, 0]);
+}
@@ -0,0 +1,280 @@
+// src/progress.rs
+
+use crate::error::DatapumpError;
+use crate::jobs::Job;
+use crate::ragged::RaggedRowCounts;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How often `Loading` progress is reported while records are flowing.
+const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The stages a migration goes through, in order.
+///
+/// `Paused` interrupts `Loading` while the user has paused the job.
+/// `Done`, `Failed` and `Cancelled` are final: exactly one of them ends every job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Connecting,
+    Sniffing,
+    CreatingTable,
+    Loading,
+    Paused,
+    Finalizing,
+    Verifying,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl Stage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stage::Connecting => "connecting",
+            Stage::Sniffing => "sniffing",
+            Stage::CreatingTable => "creating_table",
+            Stage::Loading => "loading",
+            Stage::Paused => "paused",
+            Stage::Finalizing => "finalizing",
+            Stage::Verifying => "verifying",
+            Stage::Done => "done",
+            Stage::Failed => "failed",
+            Stage::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_final(self) -> bool {
+        matches!(self, Stage::Done | Stage::Failed | Stage::Cancelled)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProgressEvent {
+    /// Id of the job this event belongs to (`None` for events outside a job).
+    pub job_id: Option<String>,
+    pub stage: Stage,
+    pub processed_rows: usize,
+    pub bytes_read: u64,
+    /// Size of the input, when known.
+    pub total_bytes: Option<u64>,
+    /// Load rate since the `Loading` stage started, not counting time paused.
+    pub rows_per_sec: f64,
+    /// Estimated seconds until the load finishes, once there is enough to go on.
+    pub eta_secs: Option<f64>,
+    /// Seconds since the job started.
+    pub elapsed_secs: f64,
+    /// Rows with the wrong number of fields that were fitted or rejected,
+    /// once the job is done and if there were any.
+    pub ragged_rows: Option<RaggedRowCounts>,
+    pub message: Option<String>,
+}
+
+impl ProgressEvent {
+    /// An event that isn't tied to a job, e.g. while sniffing a file for the UI.
+    pub fn new(stage: Stage, message: Option<String>) -> Self {
+        ProgressEvent {
+            job_id: None,
+            stage,
+            processed_rows: 0,
+            bytes_read: 0,
+            total_bytes: None,
+            rows_per_sec: 0.0,
+            eta_secs: None,
+            elapsed_secs: 0.0,
+            ragged_rows: None,
+            message,
+        }
+    }
+
+    /// Fraction of the input read so far, when the total size is known.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total_bytes {
+            Some(total) if total > 0 => Some((self.bytes_read as f64 / total as f64).min(1.0)),
+            _ => None,
+        }
+    }
+}
+
+/// Receives progress events from a running migration.
+///
+/// The GUI forwards them to the webview, the CLI prints them to stderr.
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, event: ProgressEvent);
+}
+
+/// Builds the progress events for one job and decides when to send them.
+///
+/// Stage changes are always reported; `advance` calls are throttled to one
+/// event per `REPORT_INTERVAL`, so loops can call it after every batch.
+/// Every event is also recorded as the job's live status.
+pub struct Tracker<'a> {
+    sink: &'a dyn ProgressSink,
+    job: &'a Job,
+    stage: Stage,
+    started: Instant,
+    loading_started: Option<Instant>,
+    paused_since: Option<Instant>,
+    paused_for: Duration,
+    last_report: Option<Instant>,
+    processed_rows: usize,
+    bytes_read: u64,
+    total_bytes: Option<u64>,
+    /// Rows and bytes already loaded by an earlier run of a resumed job;
+    /// they count towards progress but not towards the rate.
+    resumed_rows: usize,
+    resumed_bytes: u64,
+    ragged_rows: Option<RaggedRowCounts>,
+}
+
+impl<'a> Tracker<'a> {
+    pub fn new(sink: &'a dyn ProgressSink, job: &'a Job) -> Self {
+        Tracker {
+            sink,
+            job,
+            stage: Stage::Connecting,
+            started: Instant::now(),
+            loading_started: None,
+            paused_since: None,
+            paused_for: Duration::ZERO,
+            last_report: None,
+            processed_rows: 0,
+            bytes_read: 0,
+            total_bytes: None,
+            resumed_rows: 0,
+            resumed_bytes: 0,
+            ragged_rows: None,
+        }
+    }
+
+    pub fn job(&self) -> &Job {
+        self.job
+    }
+
+    pub fn set_total_bytes(&mut self, total_bytes: Option<u64>) {
+        self.total_bytes = total_bytes;
+    }
+
+    /// Start counting from rows and bytes an earlier run already loaded.
+    pub fn resume_from(&mut self, processed_rows: usize, bytes_read: u64) {
+        self.processed_rows = processed_rows;
+        self.bytes_read = bytes_read;
+        self.resumed_rows = processed_rows;
+        self.resumed_bytes = bytes_read;
+    }
+
+    /// Report how ragged rows were handled in the events from now on.
+    pub fn set_ragged_rows(&mut self, counts: RaggedRowCounts) {
+        self.ragged_rows = (!counts.is_empty()).then_some(counts);
+    }
+
+    /// Move to `stage` and report it straight away.
+    pub fn stage(&mut self, stage: Stage, message: Option<String>) {
+        if stage == Stage::Loading && self.loading_started.is_none() {
+            self.loading_started = Some(Instant::now());
+        }
+        if stage == Stage::Paused {
+            self.paused_since.get_or_insert_with(Instant::now);
+        } else if let Some(since) = self.paused_since.take() {
+            self.paused_for += since.elapsed();
+        }
+        self.stage = stage;
+        self.report(message);
+    }
+
+    /// Record the rows written and bytes consumed so far, reporting at most
+    /// once per `REPORT_INTERVAL`.
+    pub fn advance(&mut self, processed_rows: usize, bytes_read: u64) {
+        self.processed_rows = processed_rows;
+        self.bytes_read = bytes_read;
+
+        let due = self
+            .last_report
+            .map_or(true, |last| last.elapsed() >= REPORT_INTERVAL);
+        if due {
+            self.report(None);
+        }
+    }
+
+    /// Report the final stage for a job that ended with `result`, unless a
+    /// final stage was already reported.
+    pub fn finish<T>(&mut self, result: &Result<T, DatapumpError>) {
+        if self.stage.is_final() {
+            return;
+        }
+        match result {
+            Ok(_) => self.stage(Stage::Done, None),
+            Err(e) if e.is_cancelled() => self.stage(Stage::Cancelled, Some(e.to_string())),
+            Err(e) => self.stage(Stage::Failed, Some(e.to_string())),
+        }
+    }
+
+    fn report(&mut self, message: Option<String>) {
+        let event = self.event(message);
+        self.job.update(&event);
+        self.sink.report(event);
+        self.last_report = Some(Instant::now());
+    }
+
+    fn event(&self, message: Option<String>) -> ProgressEvent {
+        let paused = self.paused_for + self.paused_since.map_or(Duration::ZERO, |p| p.elapsed());
+        let loading_secs = self
+            .loading_started
+            .map(|started| started.elapsed().saturating_sub(paused).as_secs_f64())
+            .unwrap_or(0.0);
+        let rows_per_sec = if loading_secs > 0.0 {
+            self.processed_rows.saturating_sub(self.resumed_rows) as f64 / loading_secs
+        } else {
+            0.0
+        };
+
+        // Estimate from bytes rather than rows: rows vary in size, but the
+        // input size is known up front.
+        let loaded_bytes = self.bytes_read.saturating_sub(self.resumed_bytes);
+        let eta_secs = match self.total_bytes {
+            Some(total) if self.stage == Stage::Loading && loaded_bytes > 0 => {
+                let remaining = total.saturating_sub(self.bytes_read) as f64;
+                Some(loading_secs * remaining / loaded_bytes as f64)
+            }
+            _ => None,
+        };
+
+        ProgressEvent {
+            job_id: Some(self.job.id().to_string()),
+            stage: self.stage,
+            processed_rows: self.processed_rows,
+            bytes_read: self.bytes_read,
+            total_bytes: self.total_bytes,
+            rows_per_sec,
+            eta_secs,
+            elapsed_secs: self.started.elapsed().as_secs_f64(),
+            ragged_rows: self.ragged_rows,
+            message,
+        }
+    }
+}
+
+/// Prints one line per event to stderr, for headless use.
+pub struct StderrProgress;
+
+impl ProgressSink for StderrProgress {
+    fn report(&self, event: ProgressEvent) {
+        let mut line = format!("[{}]", event.stage.as_str());
+        if event.processed_rows > 0 {
+            line.push_str(&format!(" {} rows", event.processed_rows));
+        }
+        if let Some(fraction) = event.fraction() {
+            line.push_str(&format!(" ({:.1}%)", fraction * 100.0));
+        }
+        if event.rows_per_sec > 0.0 {
+            line.push_str(&format!(" {:.0} rows/s", event.rows_per_sec));
+        }
+        if let Some(eta) = event.eta_secs {
+            line.push_str(&format!(" eta {:.0}s", eta));
+        }
+        if let Some(message) = &event.message {
+            line.push_str(&format!(" {}", message));
+        }
+        eprintln!("{}", line);
+    }
+}
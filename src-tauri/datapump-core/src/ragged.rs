@@ -0,0 +1,330 @@
+// src/ragged.rs
+
+use crate::error::{Context, DatapumpError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+
+/// What to do with a row whose number of fields differs from the header's,
+/// or from the first row's when there is no header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RaggedRows {
+    /// Fail the job.
+    #[default]
+    Error,
+    /// Fill in missing fields with NULL; fail on rows with too many.
+    Pad,
+    /// Drop extra fields; fail on rows with too few.
+    Truncate,
+    /// Fill in missing fields with NULL and drop extra ones.
+    PadAndTruncate,
+    /// Leave the row out and write it to the reject file.
+    Reject,
+}
+
+/// How a job handles ragged rows.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RaggedRowPolicy {
+    #[serde(skip_serializing_if = "is_error")]
+    pub ragged_rows: RaggedRows,
+    /// Where rejected rows are appended as CSV, each preceded by the file
+    /// and line it came from. Required to reject rows. A resumed job may
+    /// write rows rejected after its last checkpoint a second time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reject_file: Option<String>,
+}
+
+fn is_error(ragged_rows: &RaggedRows) -> bool {
+    *ragged_rows == RaggedRows::Error
+}
+
+impl RaggedRowPolicy {
+    pub fn validate(&self) -> Result<(), DatapumpError> {
+        if self.ragged_rows == RaggedRows::Reject && self.reject_file.is_none() {
+            return Err(DatapumpError::invalid(
+                "A reject file is needed to reject ragged rows",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether rows of any length are read, to be fitted by a `RowFitter`.
+    /// Otherwise the reader itself fails on the first ragged row.
+    pub fn is_flexible(&self) -> bool {
+        self.ragged_rows != RaggedRows::Error
+    }
+}
+
+/// How many ragged rows were fitted or rejected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RaggedRowCounts {
+    pub padded: u64,
+    pub truncated: u64,
+    pub rejected: u64,
+}
+
+impl RaggedRowCounts {
+    pub fn is_empty(&self) -> bool {
+        *self == RaggedRowCounts::default()
+    }
+}
+
+impl std::ops::Add for RaggedRowCounts {
+    type Output = RaggedRowCounts;
+
+    fn add(self, other: RaggedRowCounts) -> RaggedRowCounts {
+        RaggedRowCounts {
+            padded: self.padded + other.padded,
+            truncated: self.truncated + other.truncated,
+            rejected: self.rejected + other.rejected,
+        }
+    }
+}
+
+/// "3 padded, 1 rejected", leaving out outcomes that did not happen.
+impl fmt::Display for RaggedRowCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcomes: Vec<String> = [
+            (self.padded, "padded"),
+            (self.truncated, "truncated"),
+            (self.rejected, "rejected"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, outcome)| format!("{} {}", count, outcome))
+        .collect();
+        write!(f, "{}", outcomes.join(", "))
+    }
+}
+
+/// A row as read by either reader, string or byte based.
+pub(crate) trait Fields {
+    fn len(&self) -> usize;
+    fn push_empty(&mut self);
+    fn truncate(&mut self, len: usize);
+    fn as_bytes(&self) -> &csv::ByteRecord;
+}
+
+impl Fields for csv::StringRecord {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn push_empty(&mut self) {
+        self.push_field("");
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.truncate(len);
+    }
+
+    fn as_bytes(&self) -> &csv::ByteRecord {
+        self.as_byte_record()
+    }
+}
+
+impl Fields for csv::ByteRecord {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn push_empty(&mut self) {
+        self.push_field(b"");
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.truncate(len);
+    }
+
+    fn as_bytes(&self) -> &csv::ByteRecord {
+        self
+    }
+}
+
+/// Applies a `RaggedRowPolicy` to the rows of one file, counting what it does.
+pub(crate) struct RowFitter {
+    ragged_rows: RaggedRows,
+    reject_file: Option<String>,
+    rejects: Option<csv::Writer<File>>,
+    /// The file rows are read from, as written to the reject file.
+    file: String,
+    /// Number of fields every row should have.
+    expected: usize,
+    pub(crate) counts: RaggedRowCounts,
+}
+
+impl RowFitter {
+    pub(crate) fn new(policy: &RaggedRowPolicy, file: &str, expected: usize) -> Self {
+        RowFitter {
+            ragged_rows: policy.ragged_rows,
+            reject_file: policy.reject_file.clone(),
+            rejects: None,
+            file: file.to_string(),
+            expected,
+            counts: RaggedRowCounts::default(),
+        }
+    }
+
+    /// Make `row`, read from `line`, fit the expected number of fields.
+    /// Returns `false` if the row was rejected and is to be left out.
+    pub(crate) fn fit(
+        &mut self,
+        row: &mut impl Fields,
+        line: Option<u64>,
+    ) -> Result<bool, DatapumpError> {
+        let found = row.len();
+        if found == self.expected {
+            return Ok(true);
+        }
+        let short = found < self.expected;
+        match self.ragged_rows {
+            RaggedRows::Pad | RaggedRows::PadAndTruncate if short => {
+                while row.len() < self.expected {
+                    row.push_empty();
+                }
+                self.counts.padded += 1;
+                Ok(true)
+            }
+            RaggedRows::Truncate | RaggedRows::PadAndTruncate if !short => {
+                row.truncate(self.expected);
+                self.counts.truncated += 1;
+                Ok(true)
+            }
+            RaggedRows::Reject => {
+                self.reject(row.as_bytes(), line)?;
+                self.counts.rejected += 1;
+                Ok(false)
+            }
+            _ => Err(DatapumpError::parse(
+                format!("Expected {} fields, found {}", self.expected, found),
+                line,
+            )),
+        }
+    }
+
+    /// Append a row to the reject file, opening it on the first reject.
+    /// Each write is flushed, so rows rejected before a crash are not lost.
+    fn reject(&mut self, row: &csv::ByteRecord, line: Option<u64>) -> Result<(), DatapumpError> {
+        if self.rejects.is_none() {
+            let path = self
+                .reject_file
+                .as_deref()
+                .ok_or_else(|| DatapumpError::invalid("No reject file for ragged rows"))?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .context("Failed to open reject file")?;
+            self.rejects = Some(csv::WriterBuilder::new().flexible(true).from_writer(file));
+        }
+        let Some(rejects) = self.rejects.as_mut() else {
+            return Ok(());
+        };
+
+        let line = line.map(|line| line.to_string()).unwrap_or_default();
+        let mut record = csv::ByteRecord::new();
+        record.push_field(self.file.as_bytes());
+        record.push_field(line.as_bytes());
+        for field in row {
+            record.push_field(field);
+        }
+        rejects
+            .write_byte_record(&record)
+            .and_then(|_| rejects.flush().map_err(csv::Error::from))
+            .map_err(|e| DatapumpError::io(format!("Failed to write reject file: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fitter(ragged_rows: RaggedRows, reject_file: Option<String>) -> RowFitter {
+        let policy = RaggedRowPolicy {
+            ragged_rows,
+            reject_file,
+        };
+        RowFitter::new(&policy, "sales.csv", 3)
+    }
+
+    fn row(fields: &[&str]) -> csv::StringRecord {
+        csv::StringRecord::from(fields.to_vec())
+    }
+
+    #[test]
+    fn rows_that_fit_are_left_alone() {
+        let mut fitter = fitter(RaggedRows::Error, None);
+        let mut record = row(&["1", "2", "3"]);
+        assert!(fitter.fit(&mut record, Some(2)).unwrap());
+        assert_eq!(record, row(&["1", "2", "3"]));
+        assert!(fitter.counts.is_empty());
+    }
+
+    #[test]
+    fn error_fails_on_any_ragged_row() {
+        let mut fitter = fitter(RaggedRows::Error, None);
+        let error = fitter.fit(&mut row(&["1", "2"]), Some(4)).unwrap_err();
+        assert!(error.to_string().contains("Expected 3 fields, found 2"));
+        assert!(fitter.fit(&mut row(&["1", "2", "3", "4"]), None).is_err());
+    }
+
+    #[test]
+    fn pad_fills_short_rows_only() {
+        let mut fitter = fitter(RaggedRows::Pad, None);
+        let mut record = row(&["1"]);
+        assert!(fitter.fit(&mut record, None).unwrap());
+        assert_eq!(record, row(&["1", "", ""]));
+        assert!(fitter.fit(&mut row(&["1", "2", "3", "4"]), None).is_err());
+        assert_eq!(fitter.counts.padded, 1);
+    }
+
+    #[test]
+    fn truncate_cuts_long_rows_only() {
+        let mut fitter = fitter(RaggedRows::Truncate, None);
+        let mut record = csv::ByteRecord::from(vec!["1", "2", "3", "4"]);
+        assert!(fitter.fit(&mut record, None).unwrap());
+        assert_eq!(record, csv::ByteRecord::from(vec!["1", "2", "3"]));
+        assert!(fitter.fit(&mut row(&["1", "2"]), None).is_err());
+        assert_eq!(fitter.counts.truncated, 1);
+    }
+
+    #[test]
+    fn pad_and_truncate_fits_both() {
+        let mut fitter = fitter(RaggedRows::PadAndTruncate, None);
+        let (mut short, mut long) = (row(&["1", "2"]), row(&["1", "2", "3", "4", "5"]));
+        assert!(fitter.fit(&mut short, None).unwrap());
+        assert!(fitter.fit(&mut long, None).unwrap());
+        assert_eq!((short.len(), long.len()), (3, 3));
+        assert_eq!(fitter.counts.to_string(), "1 padded, 1 truncated");
+    }
+
+    #[test]
+    fn reject_writes_rows_with_file_and_line() {
+        let path = std::env::temp_dir().join(format!("ragged-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let reject_file = Some(path.to_string_lossy().into_owned());
+
+        let mut fitter = fitter(RaggedRows::Reject, reject_file);
+        assert!(!fitter.fit(&mut row(&["1", "a,b"]), Some(7)).unwrap());
+        assert!(!fitter.fit(&mut row(&["1", "2", "3", "4"]), None).unwrap());
+        assert!(fitter.fit(&mut row(&["1", "2", "3"]), Some(9)).unwrap());
+        assert_eq!(fitter.counts.rejected, 2);
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written, "sales.csv,7,1,\"a,b\"\nsales.csv,,1,2,3,4\n");
+    }
+
+    #[test]
+    fn reject_needs_a_file() {
+        let policy = RaggedRowPolicy {
+            ragged_rows: RaggedRows::Reject,
+            reject_file: None,
+        };
+        assert!(policy.validate().is_err());
+        let mut fitter = fitter(RaggedRows::Reject, None);
+        assert!(fitter.fit(&mut row(&["1"]), None).is_err());
+    }
+}
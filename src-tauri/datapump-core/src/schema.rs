@@ -0,0 +1,161 @@
+// src/schema.rs
+
+use crate::dialect::Dialect;
+use crate::error::DatapumpError;
+use crate::types::ColumnType;
+use serde::{Deserialize, Serialize};
+
+/// The columns a file is loaded as and the dialect it was read in, as
+/// `get_csv_schema` infers them or as written by hand. Column names may hold
+/// any character, including ',' and ':'.
+///
+/// A schema that deserializes is valid.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SchemaInput")]
+pub struct Schema {
+    pub columns: Vec<SchemaColumn>,
+    /// How the file is written. When given, the file is read this way
+    /// rather than sniffed again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<Dialect>,
+}
+
+/// One column of a `Schema`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaColumn {
+    /// The header as written, or `None` when the file has no header row or
+    /// the column was not read from the file.
+    #[serde(default)]
+    pub original: Option<String>,
+    /// The name the column is loaded as.
+    pub name: String,
+    /// The logical type, such as "integer" or "date", without its format.
+    #[serde(rename = "type")]
+    pub column_type: String,
+    /// Whether the sample had empty values. Only informational: NOT NULL is
+    /// up to the job's constraints.
+    #[serde(default = "default_nullable")]
+    pub nullable: bool,
+    /// How the values of a number, date or time are written, e.g.
+    /// "1.234,56" or "%d/%m/%Y".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// A few distinct values from the sample.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub samples: Vec<String>,
+    /// How sure inference is of the type and format, from 0 to 1. A type
+    /// given by hand is certain.
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+fn default_confidence() -> f64 {
+    1.0
+}
+
+impl SchemaColumn {
+    /// A nullable column of `column_type`, with no sampled values.
+    pub fn new(name: impl Into<String>, column_type: &ColumnType) -> Self {
+        SchemaColumn {
+            original: None,
+            name: name.into(),
+            column_type: column_type.base_name(),
+            nullable: true,
+            format: column_type.format().map(str::to_string),
+            samples: Vec::new(),
+            confidence: 1.0,
+        }
+    }
+
+    /// The type of the column, with its format.
+    pub fn parsed_type(&self) -> ColumnType {
+        ColumnType::parse(&self.column_type).with_format(self.format.clone())
+    }
+
+    /// Give the column the type `typ`, written as in a schema, in place of
+    /// the one it has.
+    pub fn set_type(&mut self, typ: &str) {
+        let column_type = ColumnType::parse(typ);
+        self.column_type = column_type.base_name();
+        self.format = column_type.format().map(str::to_string);
+        self.confidence = 1.0;
+    }
+}
+
+impl Schema {
+    /// Read a schema given as JSON.
+    pub fn parse(text: &str) -> Result<Schema, DatapumpError> {
+        serde_json::from_str(text)
+            .map_err(|e| DatapumpError::invalid(format!("Invalid schema: {}", e)))
+    }
+
+    /// A schema of columns given as (name, type) pairs.
+    pub fn from_columns(columns: &[(String, String)]) -> Schema {
+        Schema {
+            columns: columns
+                .iter()
+                .map(|(name, typ)| SchemaColumn::new(name, &ColumnType::parse(typ)))
+                .collect(),
+            dialect: None,
+        }
+    }
+
+    /// The columns as (name, type) pairs, each type with its format, as
+    /// they are loaded.
+    pub fn column_definitions(&self) -> Vec<(String, String)> {
+        self.columns
+            .iter()
+            .map(|column| (column.name.clone(), column.parsed_type().name()))
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), DatapumpError> {
+        if self.columns.is_empty() {
+            return Err(DatapumpError::invalid("Schema cannot be empty"));
+        }
+        for column in &self.columns {
+            if column.name.trim().is_empty() || column.column_type.trim().is_empty() {
+                return Err(DatapumpError::invalid(format!(
+                    "Empty name or type in schema: '{}:{}'",
+                    column.name, column.column_type
+                )));
+            }
+            if column.format.is_some() && !column.parsed_type().takes_format() {
+                return Err(DatapumpError::invalid(format!(
+                    "Column '{}' has a format, but only numbers, dates and times take one",
+                    column.name
+                )));
+            }
+        }
+        match &self.dialect {
+            Some(dialect) => dialect.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A schema as written, before it is validated.
+#[derive(Deserialize)]
+#[serde(rename = "Schema")]
+struct SchemaInput {
+    columns: Vec<SchemaColumn>,
+    #[serde(default)]
+    dialect: Option<Dialect>,
+}
+
+impl TryFrom<SchemaInput> for Schema {
+    type Error = DatapumpError;
+
+    fn try_from(input: SchemaInput) -> Result<Self, Self::Error> {
+        let schema = Schema {
+            columns: input.columns,
+            dialect: input.dialect,
+        };
+        schema.validate()?;
+        Ok(schema)
+    }
+}
@@ -0,0 +1,371 @@
+// src/sink.rs
+
+use crate::checkpoint::SourcePosition;
+use crate::constraints::Constraints;
+use crate::error::{Context, DatapumpError};
+use crate::migrate::LoadMode;
+use crate::pipeline;
+use crate::postgres_writer::{self, CopyWriter};
+use crate::source::Record;
+use crate::sqlite_writer;
+use async_trait::async_trait;
+use bytes::BytesMut;
+use futures_util::SinkExt;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio_postgres::Client;
+
+/// A destination that typed records can be written to.
+///
+/// The lifecycle is `create` once, `write_batch` any number of times, then
+/// exactly one of `commit` (success) or `abort` (error or cancellation).
+/// Each batch is committed together with the job's checkpoint, so after a
+/// crash `committed_checkpoint` says exactly which rows the destination has.
+#[async_trait]
+pub trait Sink: Send {
+    /// Create the target (table, file, ...) according to `mode`, with
+    /// `constraints` if it is new, and prepare for writing.
+    async fn create(
+        &mut self,
+        columns: &[(String, String)],
+        constraints: &Constraints,
+        mode: LoadMode,
+    ) -> Result<(), DatapumpError>;
+
+    /// The source position and total row count this job last committed here.
+    async fn committed_checkpoint(
+        &mut self,
+    ) -> Result<Option<(SourcePosition, usize)>, DatapumpError>;
+
+    /// Write a batch of records and commit it, recording that the job has now
+    /// loaded `rows_committed` rows, up to `position` in the source.
+    async fn write_batch(
+        &mut self,
+        records: Vec<Record>,
+        position: SourcePosition,
+        rows_committed: usize,
+    ) -> Result<(), DatapumpError>;
+
+    /// Finish loading, clear the job's checkpoint and return the number of
+    /// rows the destination reports as loaded by this sink.
+    async fn commit(&mut self) -> Result<u64, DatapumpError>;
+
+    /// Discard the batch in flight, if any.
+    async fn abort(&mut self) -> Result<(), DatapumpError>;
+
+    /// Called periodically while the job is paused, so an idle connection is
+    /// not dropped by the server or the network in between.
+    async fn keepalive(&mut self) -> Result<(), DatapumpError> {
+        Ok(())
+    }
+}
+
+pub(crate) fn encode_position(position: &SourcePosition) -> String {
+    serde_json::to_string(position).unwrap_or_default()
+}
+
+fn decode_checkpoint(
+    position: &str,
+    rows_committed: i64,
+) -> Result<(SourcePosition, usize), DatapumpError> {
+    let position = serde_json::from_str(position)
+        .map_err(|e| DatapumpError::invalid(format!("Invalid stored checkpoint: {}", e)))?;
+    Ok((position, rows_committed.max(0) as usize))
+}
+
+/// Writes into a SQLite table, committing one transaction per batch.
+///
+/// Every SQLite call runs on the blocking thread pool, so a slow disk or a
+/// locked database never stalls the async runtime.
+pub struct SqliteSink {
+    connection: Arc<Mutex<sqlite::Connection>>,
+    table_name: String,
+    job_id: String,
+    columns: Arc<[(String, String)]>,
+    rows_written: u64,
+}
+
+impl SqliteSink {
+    pub fn open(db_path: &str, table_name: &str, job_id: &str) -> Result<Self, DatapumpError> {
+        Ok(SqliteSink {
+            connection: Arc::new(Mutex::new(sqlite_writer::open_connection(db_path)?)),
+            table_name: table_name.to_string(),
+            job_id: job_id.to_string(),
+            columns: Arc::new([]),
+            rows_written: 0,
+        })
+    }
+
+    /// Run `f` against the connection on the blocking thread pool.
+    async fn with_connection<T, F>(&self, f: F) -> Result<T, DatapumpError>
+    where
+        F: FnOnce(&sqlite::Connection) -> Result<T, DatapumpError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        pipeline::blocking(move || {
+            // A panic mid-batch leaves nothing half-done that ROLLBACK cannot undo
+            let connection = connection.lock().unwrap_or_else(PoisonError::into_inner);
+            f(&connection)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl Sink for SqliteSink {
+    async fn create(
+        &mut self,
+        columns: &[(String, String)],
+        constraints: &Constraints,
+        mode: LoadMode,
+    ) -> Result<(), DatapumpError> {
+        let table_name = self.table_name.clone();
+        let table_columns = columns.to_vec();
+        let constraints = constraints.clone();
+        self.with_connection(move |connection| {
+            let exists = sqlite_writer::table_exists(connection, &table_name)?;
+            if mode == LoadMode::Create && exists {
+                return Err(DatapumpError::invalid(format!(
+                    "Table '{}' already exists",
+                    table_name
+                )));
+            }
+
+            // Replacing or emptying the table inside the load transaction means
+            // a load that fails before its first commit keeps the old table
+            sqlite_writer::begin_transaction(connection)?;
+            match mode {
+                LoadMode::Replace if exists => sqlite_writer::drop_table(connection, &table_name)?,
+                LoadMode::Truncate if exists => {
+                    sqlite_writer::truncate_table(connection, &table_name)?
+                }
+                _ => {}
+            }
+            sqlite_writer::create_table(connection, &table_name, &table_columns, &constraints)?;
+            Ok(())
+        })
+        .await?;
+        self.columns = columns.into();
+        Ok(())
+    }
+
+    async fn committed_checkpoint(
+        &mut self,
+    ) -> Result<Option<(SourcePosition, usize)>, DatapumpError> {
+        let job_id = self.job_id.clone();
+        self.with_connection(move |connection| sqlite_writer::load_checkpoint(connection, &job_id))
+            .await?
+            .map(|(position, rows)| decode_checkpoint(&position, rows))
+            .transpose()
+    }
+
+    async fn write_batch(
+        &mut self,
+        records: Vec<Record>,
+        position: SourcePosition,
+        rows_committed: usize,
+    ) -> Result<(), DatapumpError> {
+        let table_name = self.table_name.clone();
+        let job_id = self.job_id.clone();
+        let columns = Arc::clone(&self.columns);
+        let written = self
+            .with_connection(move |connection| {
+                let changes_before = connection.total_change_count();
+                {
+                    let mut statement =
+                        sqlite_writer::prepare_insert(connection, &table_name, &columns)?;
+                    for record in &records {
+                        sqlite_writer::insert_values(&mut statement, record)?;
+                    }
+                }
+                let written = (connection.total_change_count() - changes_before) as u64;
+                // Created with the first checkpoint, as a finished job drops it
+                sqlite_writer::create_checkpoint_table(connection)?;
+                sqlite_writer::save_checkpoint(
+                    connection,
+                    &job_id,
+                    &encode_position(&position),
+                    rows_committed as i64,
+                )?;
+                sqlite_writer::commit_and_begin_new_transaction(connection)?;
+                Ok(written)
+            })
+            .await?;
+        self.rows_written += written;
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> Result<u64, DatapumpError> {
+        let job_id = self.job_id.clone();
+        self.with_connection(move |connection| {
+            sqlite_writer::delete_checkpoint(connection, &job_id)?;
+            sqlite_writer::commit_transaction(connection)
+        })
+        .await?;
+        Ok(self.rows_written)
+    }
+
+    async fn abort(&mut self) -> Result<(), DatapumpError> {
+        self.with_connection(sqlite_writer::rollback_transaction)
+            .await
+    }
+}
+
+/// Writes into a PostgreSQL table with one text-format COPY per batch, each
+/// in its own transaction together with the job's checkpoint.
+pub struct PostgresSink {
+    client: Client,
+    table_name: String,
+    job_id: String,
+    columns: Vec<(String, String)>,
+    writer: Option<CopyWriter>,
+    /// Whether a transaction is open that the next batch should commit.
+    in_transaction: bool,
+    rows_written: u64,
+}
+
+impl PostgresSink {
+    pub async fn open(
+        connection_string: &str,
+        table_name: &str,
+        job_id: &str,
+    ) -> Result<Self, DatapumpError> {
+        Ok(PostgresSink {
+            client: postgres_writer::open_connection(connection_string).await?,
+            table_name: table_name.to_string(),
+            job_id: job_id.to_string(),
+            columns: Vec::new(),
+            writer: None,
+            in_transaction: false,
+            rows_written: 0,
+        })
+    }
+
+    async fn begin(&mut self) -> Result<(), DatapumpError> {
+        if !self.in_transaction {
+            self.client
+                .batch_execute("BEGIN")
+                .await
+                .context("Failed to start transaction")?;
+            self.in_transaction = true;
+        }
+        Ok(())
+    }
+
+    async fn commit_transaction(&mut self) -> Result<(), DatapumpError> {
+        if self.in_transaction {
+            self.client
+                .batch_execute("COMMIT")
+                .await
+                .context("Failed to commit batch")?;
+            self.in_transaction = false;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for PostgresSink {
+    async fn create(
+        &mut self,
+        columns: &[(String, String)],
+        constraints: &Constraints,
+        mode: LoadMode,
+    ) -> Result<(), DatapumpError> {
+        // Replacing or emptying the table in the first batch's transaction
+        // means an aborted load keeps the old rows
+        self.begin().await?;
+        let exists = postgres_writer::table_exists(&self.client, &self.table_name).await?;
+        match mode {
+            LoadMode::Create if exists => {
+                return Err(DatapumpError::invalid(format!(
+                    "Table '{}' already exists",
+                    self.table_name
+                )));
+            }
+            LoadMode::Replace if exists => {
+                postgres_writer::drop_table(&self.client, &self.table_name).await?;
+            }
+            LoadMode::Truncate if exists => {
+                postgres_writer::truncate_table(&self.client, &self.table_name).await?;
+            }
+            _ => {}
+        }
+
+        postgres_writer::create_table(&self.client, &self.table_name, columns, constraints).await?;
+        self.columns = columns.to_vec();
+        Ok(())
+    }
+
+    async fn committed_checkpoint(
+        &mut self,
+    ) -> Result<Option<(SourcePosition, usize)>, DatapumpError> {
+        postgres_writer::load_checkpoint(&self.client, &self.job_id)
+            .await?
+            .map(|(position, rows)| decode_checkpoint(&position, rows))
+            .transpose()
+    }
+
+    async fn write_batch(
+        &mut self,
+        records: Vec<Record>,
+        position: SourcePosition,
+        rows_committed: usize,
+    ) -> Result<(), DatapumpError> {
+        let mut buf = BytesMut::with_capacity(records.len() * 64);
+        for record in &records {
+            postgres_writer::encode_text_row(&mut buf, record);
+        }
+
+        self.begin().await?;
+
+        // Kept in `self` while in flight so `abort` can cancel it
+        let writer = self.writer.insert(
+            postgres_writer::start_text_copy(&self.client, &self.table_name, &self.columns).await?,
+        );
+        writer.send(buf).await.context("Failed to write batch")?;
+        let written = match self.writer.take() {
+            Some(writer) => postgres_writer::finish_copy(writer).await?,
+            None => 0,
+        };
+
+        postgres_writer::create_checkpoint_table(&self.client).await?;
+        postgres_writer::save_checkpoint(
+            &self.client,
+            &self.job_id,
+            &encode_position(&position),
+            rows_committed as i64,
+        )
+        .await?;
+        self.commit_transaction().await?;
+
+        self.rows_written += written;
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> Result<u64, DatapumpError> {
+        // A load with no rows commits the table it created or emptied here
+        self.begin().await?;
+        postgres_writer::delete_checkpoint(&self.client, &self.job_id).await?;
+        self.commit_transaction().await?;
+        Ok(self.rows_written)
+    }
+
+    async fn abort(&mut self) -> Result<(), DatapumpError> {
+        // Dropping an unfinished COPY sink makes the server abort the COPY;
+        // rolling back then discards the rest of the batch's transaction.
+        self.writer.take();
+        self.in_transaction = false;
+        self.client
+            .batch_execute("ROLLBACK")
+            .await
+            .context("Failed to ROLLBACK")
+    }
+
+    async fn keepalive(&mut self) -> Result<(), DatapumpError> {
+        self.client
+            .batch_execute("SELECT 1")
+            .await
+            .context("Lost connection while paused")
+    }
+}
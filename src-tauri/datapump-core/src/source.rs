@@ -0,0 +1,371 @@
+// src/source.rs
+
+use crate::checkpoint::SourcePosition;
+use crate::compression;
+use crate::csv_reader;
+use crate::dialect::Dialect;
+use crate::error::{Context, DatapumpError};
+use crate::input::CsvInput;
+use crate::ragged::{RaggedRowCounts, RaggedRowPolicy, RowFitter};
+use crate::types::ColumnType;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use std::fmt;
+
+/// A single typed field value produced by a `Source`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Boolean(bool),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Timestamp(NaiveDateTime),
+    TimestampTz(DateTime<FixedOffset>),
+}
+
+impl Value {
+    /// Convert a raw text field into a typed value according to the column type.
+    /// Values that don't parse as the declared type are kept as text so the
+    /// destination can decide what to do with them.
+    pub fn from_field(field: &str, column_type: &str) -> Value {
+        ColumnType::parse(column_type).value(field)
+    }
+}
+
+/// Dates and times in ISO 8601, with a space between date and time, which
+/// both SQLite and PostgreSQL read back.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Real(x) => write!(f, "{}", x),
+            Value::Text(s) => write!(f, "{}", s),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Date(date) => write!(f, "{}", date),
+            Value::Time(time) => write!(f, "{}", time),
+            Value::Timestamp(timestamp) => write!(f, "{}", timestamp),
+            Value::TimestampTz(timestamp) => {
+                write!(f, "{}", timestamp.format("%Y-%m-%d %H:%M:%S%.f%:z"))
+            }
+        }
+    }
+}
+
+/// One row of typed values, in schema column order.
+pub type Record = Vec<Value>;
+
+/// One row of raw text fields as read from the source, before conversion.
+/// Its position, when set, locates the row in the input for error messages.
+pub type RawRow = csv::StringRecord;
+
+/// Convert a raw row into typed values according to the column `types`.
+///
+/// This is the CPU-bound half of reading a source, so the pipeline runs it on
+/// worker threads, separately from `Source::next_row`.
+pub fn parse_row(row: &RawRow, types: &[ColumnType]) -> Result<Record, DatapumpError> {
+    Ok(row
+        .iter()
+        .zip(types.iter())
+        .map(|(field, typ)| typ.value(field))
+        .collect())
+}
+
+/// Anything that can yield rows along with the schema describing them.
+pub trait Source: Send {
+    /// Column names and types, in the order values appear in each record.
+    fn schema(&self) -> &[(String, String)];
+
+    /// Size of the input in bytes, when known, used for progress reporting.
+    fn total_bytes(&self) -> Option<u64>;
+
+    /// Bytes of input consumed so far.
+    fn bytes_read(&self) -> u64;
+
+    /// Where the next row starts, for checkpointing.
+    fn position(&self) -> SourcePosition;
+
+    /// Continue reading from a position previously returned by `position`
+    /// on the same input.
+    fn seek(&mut self, position: SourcePosition) -> Result<(), DatapumpError>;
+
+    /// Read the next row, or `None` once the source is exhausted.
+    fn next_row(&mut self) -> Result<Option<RawRow>, DatapumpError>;
+}
+
+/// A delimited text file (CSV/TSV) read with the `csv` crate.
+pub struct CsvSource {
+    total_bytes: Option<u64>,
+    columns: Vec<(String, String)>,
+    reader: csv::Reader<CsvInput>,
+    /// Fits ragged rows to the header, unless they are an error.
+    fitter: Option<RowFitter>,
+}
+
+impl CsvSource {
+    /// Open `file_path` to be read in `dialect`, handling rows with more or
+    /// fewer fields than the header as `ragged` says.
+    pub fn open(
+        file_path: &str,
+        dialect: &Dialect,
+        columns: Vec<(String, String)>,
+        ragged: &RaggedRowPolicy,
+    ) -> Result<Self, DatapumpError> {
+        let reader = csv_reader::create_csv_reader(file_path, dialect, ragged.is_flexible())?;
+        let total_bytes = compression::input_size(file_path);
+
+        // The header, or first row, is read on its own so that the reader
+        // keeps its place at the start.
+        let fitter = if ragged.is_flexible() {
+            let expected = csv_reader::create_csv_reader(file_path, dialect, true)?
+                .byte_headers()?
+                .len();
+            Some(RowFitter::new(ragged, file_path, expected))
+        } else {
+            None
+        };
+
+        Ok(CsvSource {
+            total_bytes,
+            columns,
+            reader,
+            fitter,
+        })
+    }
+
+    fn ragged(&self) -> RaggedRowCounts {
+        self.fitter
+            .as_ref()
+            .map(|fitter| fitter.counts)
+            .unwrap_or_default()
+    }
+
+    /// Read the next record as it is, naming the column of a parse error.
+    fn read_record(&mut self, row: &mut RawRow) -> Result<bool, DatapumpError> {
+        self.reader.read_record(row).map_err(|e| {
+            match DatapumpError::from(e) {
+                // Name the column instead of numbering it
+                DatapumpError::Parse {
+                    message,
+                    line,
+                    column: Some(column),
+                    value,
+                } => {
+                    let name = column
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| self.columns.get(n.wrapping_sub(1)))
+                        .map(|(name, _)| name.clone())
+                        .unwrap_or(column);
+                    DatapumpError::Parse {
+                        message,
+                        line,
+                        column: Some(name),
+                        value,
+                    }
+                }
+                e => e,
+            }
+            .map_line(|line| self.reader.get_ref().file_line(line))
+        })
+    }
+}
+
+impl Source for CsvSource {
+    fn schema(&self) -> &[(String, String)] {
+        &self.columns
+    }
+
+    fn total_bytes(&self) -> Option<u64> {
+        self.total_bytes
+    }
+
+    /// Bytes of the file itself, which differ from the text the parser sees
+    /// when the file is compressed, transcoded or has lines left out.
+    fn bytes_read(&self) -> u64 {
+        self.reader
+            .get_ref()
+            .bytes_read(self.reader.position().byte())
+    }
+
+    fn position(&self) -> SourcePosition {
+        let position = self.reader.position();
+        SourcePosition {
+            byte: self.bytes_read(),
+            line: position.line(),
+            record: position.record(),
+            file: 0,
+            ragged: self.ragged(),
+        }
+    }
+
+    /// A compressed or transcoded file, or one with lines left out, cannot
+    /// seek to a byte offset in the text the parser sees, so it is read again
+    /// from the start and the committed records skipped.
+    fn seek(&mut self, position: SourcePosition) -> Result<(), DatapumpError> {
+        if let Some(fitter) = self.fitter.as_mut() {
+            fitter.counts = position.ragged;
+        }
+        if !self.reader.get_ref().is_seekable() {
+            let mut row = RawRow::new();
+            while self.reader.position().record() < position.record {
+                if !self.reader.read_record(&mut row)? {
+                    return Err(DatapumpError::invalid(
+                        "Checkpoint is past the end of the file",
+                    ));
+                }
+            }
+            return Ok(());
+        }
+
+        let mut pos = csv::Position::new();
+        pos.set_byte(position.byte)
+            .set_line(position.line)
+            .set_record(position.record);
+        self.reader
+            .seek(pos)
+            .context("Failed to seek to checkpoint")
+    }
+
+    fn next_row(&mut self) -> Result<Option<RawRow>, DatapumpError> {
+        let mut row = RawRow::new();
+        loop {
+            if !self.read_record(&mut row)? {
+                return Ok(None);
+            }
+            let Some(fitter) = self.fitter.as_mut() else {
+                return Ok(Some(row));
+            };
+            let line = row
+                .position()
+                .map(|position| self.reader.get_ref().file_line(position.line()));
+            if fitter.fit(&mut row, line)? {
+                return Ok(Some(row));
+            }
+        }
+    }
+}
+
+/// Delimited files with the same columns, read one after another as a
+/// single source. Progress and positions count the bytes of all the files.
+pub struct CsvFilesSource {
+    /// Each file with the dialect it is read in.
+    files: Vec<(String, Dialect)>,
+    /// Size of each file, when known; the sum of those before a file is
+    /// where it starts in the whole.
+    sizes: Vec<Option<u64>>,
+    columns: Vec<(String, String)>,
+    /// Whether each row gets the path of its file, in the last column.
+    source_file_column: bool,
+    ragged: RaggedRowPolicy,
+    /// Ragged rows fitted or rejected in the files before the current one.
+    ragged_before: RaggedRowCounts,
+    index: usize,
+    current: CsvSource,
+}
+
+impl CsvFilesSource {
+    /// Open `files` to be read in turn, starting with the first. With
+    /// `source_file_column`, `columns` ends with `SOURCE_FILE_COLUMN`.
+    pub fn open(
+        files: Vec<(String, Dialect)>,
+        columns: Vec<(String, String)>,
+        source_file_column: bool,
+        ragged: &RaggedRowPolicy,
+    ) -> Result<Self, DatapumpError> {
+        let (first, dialect) = files
+            .first()
+            .ok_or_else(|| DatapumpError::invalid("No files to load"))?;
+        let current = CsvSource::open(first, dialect, columns.clone(), ragged)?;
+        let sizes = files
+            .iter()
+            .map(|(file, _)| compression::input_size(file))
+            .collect();
+
+        Ok(CsvFilesSource {
+            files,
+            sizes,
+            columns,
+            source_file_column,
+            ragged: ragged.clone(),
+            ragged_before: RaggedRowCounts::default(),
+            index: 0,
+            current,
+        })
+    }
+
+    /// Bytes of the files before file `index`.
+    fn offset(&self, index: usize) -> u64 {
+        self.sizes[..index]
+            .iter()
+            .map(|size| size.unwrap_or(0))
+            .sum()
+    }
+
+    fn open_file(&mut self, index: usize) -> Result<(), DatapumpError> {
+        let (file, dialect) = &self.files[index];
+        self.current = CsvSource::open(file, dialect, self.columns.clone(), &self.ragged)?;
+        self.index = index;
+        Ok(())
+    }
+}
+
+impl Source for CsvFilesSource {
+    fn schema(&self) -> &[(String, String)] {
+        &self.columns
+    }
+
+    fn total_bytes(&self) -> Option<u64> {
+        self.sizes.iter().copied().sum()
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.offset(self.index) + self.current.bytes_read()
+    }
+
+    fn position(&self) -> SourcePosition {
+        SourcePosition {
+            byte: self.bytes_read(),
+            file: self.index,
+            ragged: self.ragged_before + self.current.ragged(),
+            ..self.current.position()
+        }
+    }
+
+    fn seek(&mut self, position: SourcePosition) -> Result<(), DatapumpError> {
+        if position.file >= self.files.len() {
+            return Err(DatapumpError::invalid("Checkpoint is past the last file"));
+        }
+        self.open_file(position.file)?;
+        // The counts so far are all carried by the file read from
+        self.ragged_before = RaggedRowCounts::default();
+        self.current.seek(SourcePosition {
+            byte: position.byte.saturating_sub(self.offset(position.file)),
+            file: 0,
+            ..position
+        })
+    }
+
+    fn next_row(&mut self) -> Result<Option<RawRow>, DatapumpError> {
+        loop {
+            let (file, _) = &self.files[self.index];
+            if let Some(mut row) = self
+                .current
+                .next_row()
+                .map_err(|e| e.context(&format!("In {}", file)))?
+            {
+                if self.source_file_column {
+                    row.push_field(file);
+                }
+                return Ok(Some(row));
+            }
+            if self.index + 1 == self.files.len() {
+                return Ok(None);
+            }
+            self.ragged_before = self.ragged_before + self.current.ragged();
+            self.open_file(self.index + 1)?;
+        }
+    }
+}
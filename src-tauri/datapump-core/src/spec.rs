@@ -0,0 +1,207 @@
+// src/spec.rs
+
+use crate::constraints::Constraints;
+use crate::csv_schema::Sample;
+use crate::error::{Context, DatapumpError};
+use crate::migrate::{LoadMode, SinkConfig, SourceConfig};
+use crate::ragged::RaggedRowPolicy;
+use crate::schema::{Schema, SchemaColumn};
+use crate::types::ColumnType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+fn default_batch_size() -> usize {
+    10_000
+}
+
+/// A complete, repeatable description of one migration.
+///
+/// Specs are stored as TOML or JSON (chosen by file extension) so they can be
+/// versioned alongside the data they load:
+///
+/// ```toml
+/// table_name = "sales"
+/// mode = "truncate"
+///
+/// [source]
+/// type = "csv"
+/// path = "exports/sales.csv"
+///
+/// [destination]
+/// type = "postgres"
+/// connection_string = "postgresql://user@localhost/warehouse"
+///
+/// [columns]
+/// amount = "real"
+/// ```
+///
+/// Dates and times may give the format of their values, e.g.
+/// `ordered = "date(%d/%m/%Y)"`; inferred types always do. So may numbers
+/// written other than plainly, e.g. `amount = "real(1.234,56)"`.
+///
+/// A `schema` gives every column instead of inferring them, as a table of
+/// columns in the form `get_csv_schema` returns. When it has a `dialect`,
+/// the file is read in it rather than sniffed.
+///
+/// Column types are inferred from the first 1000 rows unless a `[sample]`
+/// table says otherwise, e.g. `method = "reservoir"` with `rows = 10000`,
+/// or `method = "full"`.
+///
+/// Rows with more or fewer fields than the header fail the job unless
+/// `ragged_rows` says otherwise, e.g. `ragged_rows = "reject"` together
+/// with `reject_file = "sales.rejects.csv"`.
+///
+/// A new table has no constraints unless a `[constraints]` table gives
+/// them, e.g. `not_null = ["id", "sold_at"]` and `primary_key = ["id"]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSpec {
+    pub source: SourceConfig,
+    pub destination: SinkConfig,
+    pub table_name: String,
+    /// Every column of the source; inferred from the source when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Schema>,
+    /// Which rows the schema is inferred from, when it is not given.
+    #[serde(default, skip_serializing_if = "Sample::is_default")]
+    pub sample: Sample,
+    /// Per-column type overrides applied on top of the (inferred) schema.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub columns: BTreeMap<String, String>,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde(default)]
+    pub mode: LoadMode,
+    #[serde(flatten)]
+    pub ragged: RaggedRowPolicy,
+    /// NOT NULL columns and primary key of the table, if it is created.
+    #[serde(default, skip_serializing_if = "Constraints::is_empty")]
+    pub constraints: Constraints,
+}
+
+impl JobSpec {
+    pub fn new(
+        source: SourceConfig,
+        destination: SinkConfig,
+        table_name: impl Into<String>,
+    ) -> Self {
+        JobSpec {
+            source,
+            destination,
+            table_name: table_name.into(),
+            schema: None,
+            sample: Sample::default(),
+            columns: BTreeMap::new(),
+            batch_size: default_batch_size(),
+            mode: LoadMode::default(),
+            ragged: RaggedRowPolicy::default(),
+            constraints: Constraints::default(),
+        }
+    }
+
+    /// Read a spec from a `.toml` or `.json` file.
+    pub fn load(path: &str) -> Result<Self, DatapumpError> {
+        let text =
+            std::fs::read_to_string(path).context(&format!("Failed to read job spec {}", path))?;
+
+        let spec = if is_json(path) {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        };
+        spec.map_err(|e| DatapumpError::invalid(format!("Invalid job spec {}: {}", path, e)))
+    }
+
+    /// Write the spec to a `.toml` or `.json` file.
+    pub fn save(&self, path: &str) -> Result<(), DatapumpError> {
+        let text = if is_json(path) {
+            serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+        } else {
+            toml::to_string_pretty(self).map_err(|e| e.to_string())
+        }
+        .map_err(DatapumpError::invalid)?;
+
+        std::fs::write(path, text).context(&format!("Failed to write job spec {}", path))
+    }
+
+    /// Check the spec for problems that would make the migration fail up front.
+    pub fn validate(&self) -> Result<(), DatapumpError> {
+        if self.table_name.is_empty() {
+            return Err(DatapumpError::invalid("Table name cannot be empty"));
+        }
+        if self.batch_size == 0 {
+            return Err(DatapumpError::invalid("Batch size must be greater than 0"));
+        }
+        if let Some(schema) = &self.schema {
+            schema.validate()?;
+        }
+        self.sample.validate()?;
+        if let Some((name, _)) = self.columns.iter().find(|(_, typ)| typ.trim().is_empty()) {
+            return Err(DatapumpError::invalid(format!(
+                "Empty type override for column '{}'",
+                name
+            )));
+        }
+        self.ragged.validate()?;
+        self.source.validate()
+    }
+
+    /// The column definitions to load with: the explicit or inferred schema,
+    /// with any columns the source adds and the per-column overrides applied.
+    pub fn resolve_columns(&self) -> Result<Vec<(String, String)>, DatapumpError> {
+        Ok(self.applied(self.source_schema()?.0)?.column_definitions())
+    }
+
+    /// The explicit or inferred schema, along with the number of rows the
+    /// types were inferred from, if they were.
+    fn source_schema(&self) -> Result<(Schema, Option<u64>), DatapumpError> {
+        match &self.schema {
+            Some(schema) if !schema.columns.is_empty() => Ok((schema.clone(), None)),
+            _ => self.source.infer_schema(self.sample),
+        }
+    }
+
+    /// `schema` with the added columns and overrides applied.
+    fn applied(&self, mut schema: Schema) -> Result<Schema, DatapumpError> {
+        for (name, typ) in self.source.added_columns() {
+            if !schema.columns.iter().any(|column| column.name == name) {
+                let column_type = ColumnType::parse(&typ);
+                schema.columns.push(SchemaColumn::new(name, &column_type));
+            }
+        }
+
+        for (name, typ) in &self.columns {
+            let column = schema
+                .columns
+                .iter_mut()
+                .find(|column| column.name == *name)
+                .ok_or_else(|| {
+                    DatapumpError::invalid(format!("Type override for unknown column '{}'", name))
+                })?;
+            column.set_type(typ.trim());
+        }
+
+        Ok(schema)
+    }
+
+    /// A copy of the spec with the schema and source resolved and the
+    /// overrides folded in, so that running it again loads the same files
+    /// with exactly the same columns. Also returns the number of rows the
+    /// types were inferred from, if they were.
+    pub fn resolved(&self) -> Result<(JobSpec, Option<u64>), DatapumpError> {
+        let (schema, rows_sampled) = self.source_schema()?;
+        let spec = JobSpec {
+            source: self.source.resolved()?,
+            schema: Some(self.applied(schema)?),
+            columns: BTreeMap::new(),
+            ..self.clone()
+        };
+        Ok((spec, rows_sampled))
+    }
+}
+
+fn is_json(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
@@ -0,0 +1,270 @@
+// src/sqlite_writer.rs
+
+use crate::checkpoint::CHECKPOINT_TABLE;
+use crate::constraints::Constraints;
+use crate::error::{Context, DatapumpError};
+use crate::source::Value;
+use crate::types::ColumnType;
+use sqlite::{Connection, Statement};
+use std::thread;
+use std::time::Duration;
+
+/// Open the SQLite database and enable WAL + NORMAL synchronous mode.
+pub fn open_connection(db_path: &str) -> Result<Connection, DatapumpError> {
+    let connection = sqlite::open(db_path)?;
+
+    // WAL mode
+    connection
+        .execute("PRAGMA journal_mode=WAL;")
+        .context("Failed to set WAL mode")?;
+
+    // Synchronous = NORMAL
+    connection
+        .execute("PRAGMA synchronous=NORMAL;")
+        .context("Failed to set synchronous mode")?;
+
+    Ok(connection)
+}
+
+/// Create table if not exists, given a table name and columns.
+pub fn create_table(
+    connection: &Connection,
+    table_name: &str,
+    columns: &[(String, String)],
+    constraints: &Constraints,
+) -> Result<(), DatapumpError> {
+    let create_table_sql = format!(
+        "CREATE TABLE IF NOT EXISTS \"{}\" ({})",
+        table_name,
+        constraints.table_definition(columns, ColumnType::sqlite_type)
+    );
+
+    connection
+        .execute(&create_table_sql)
+        .context("Failed to create table")?;
+
+    Ok(())
+}
+
+/// Check whether a table with the given name exists.
+pub fn table_exists(connection: &Connection, table_name: &str) -> Result<bool, DatapumpError> {
+    let mut statement =
+        connection.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")?;
+    statement.bind((1, table_name))?;
+
+    match statement.next() {
+        Ok(sqlite::State::Row) => Ok(true),
+        Ok(sqlite::State::Done) => Ok(false),
+        Err(e) => Err(DatapumpError::from(e).context("Failed to check if table exists")),
+    }
+}
+
+/// Drop the table if it exists.
+pub fn drop_table(connection: &Connection, table_name: &str) -> Result<(), DatapumpError> {
+    connection
+        .execute(format!("DROP TABLE IF EXISTS \"{}\"", table_name))
+        .context("Failed to drop table")
+}
+
+/// Delete every row from the table.
+pub fn truncate_table(connection: &Connection, table_name: &str) -> Result<(), DatapumpError> {
+    connection
+        .execute(format!("DELETE FROM \"{}\"", table_name))
+        .context("Failed to truncate table")
+}
+
+/// Create the table that records each job's committed checkpoint.
+pub fn create_checkpoint_table(connection: &Connection) -> Result<(), DatapumpError> {
+    connection
+        .execute(format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (job_id TEXT PRIMARY KEY, position TEXT NOT NULL, rows_committed INTEGER NOT NULL)",
+            CHECKPOINT_TABLE
+        ))
+        .context("Failed to create checkpoint table")
+}
+
+/// Record a job's checkpoint; meant to run inside the batch's transaction.
+pub fn save_checkpoint(
+    connection: &Connection,
+    job_id: &str,
+    position: &str,
+    rows_committed: i64,
+) -> Result<(), DatapumpError> {
+    let mut statement = connection.prepare(format!(
+        "INSERT OR REPLACE INTO \"{}\" (job_id, position, rows_committed) VALUES (?, ?, ?)",
+        CHECKPOINT_TABLE
+    ))?;
+    statement.bind((1, job_id))?;
+    statement.bind((2, position))?;
+    statement.bind((3, rows_committed))?;
+    statement.next().context("Failed to save checkpoint")?;
+    Ok(())
+}
+
+/// The committed position and row count of a job, if it has a checkpoint.
+pub fn load_checkpoint(
+    connection: &Connection,
+    job_id: &str,
+) -> Result<Option<(String, i64)>, DatapumpError> {
+    if !table_exists(connection, CHECKPOINT_TABLE)? {
+        return Ok(None);
+    }
+    let mut statement = connection.prepare(format!(
+        "SELECT position, rows_committed FROM \"{}\" WHERE job_id = ?",
+        CHECKPOINT_TABLE
+    ))?;
+    statement.bind((1, job_id))?;
+
+    match statement.next().context("Failed to load checkpoint")? {
+        sqlite::State::Row => Ok(Some((
+            statement.read::<String, _>(0)?,
+            statement.read::<i64, _>(1)?,
+        ))),
+        sqlite::State::Done => Ok(None),
+    }
+}
+
+/// Remove a job's checkpoint once it has completed, and the checkpoint table
+/// once no job has one.
+pub fn delete_checkpoint(connection: &Connection, job_id: &str) -> Result<(), DatapumpError> {
+    if !table_exists(connection, CHECKPOINT_TABLE)? {
+        return Ok(());
+    }
+    let mut statement = connection.prepare(format!(
+        "DELETE FROM \"{}\" WHERE job_id = ?",
+        CHECKPOINT_TABLE
+    ))?;
+    statement.bind((1, job_id))?;
+    statement.next().context("Failed to delete checkpoint")?;
+
+    let empty = {
+        let mut statement = connection.prepare(format!(
+            "SELECT NOT EXISTS (SELECT 1 FROM \"{}\")",
+            CHECKPOINT_TABLE
+        ))?;
+        statement.next().context("Failed to delete checkpoint")?;
+        statement.read::<i64, _>(0)? == 1
+    };
+    if empty {
+        drop_table(connection, CHECKPOINT_TABLE)?;
+    }
+    Ok(())
+}
+
+/// Prepare the INSERT statement for the given columns.
+pub fn prepare_insert<'a>(
+    connection: &'a Connection,
+    table_name: &'a str,
+    columns: &'a [(String, String)],
+) -> Result<Statement<'a>, DatapumpError> {
+    let placeholders = columns.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    let column_names = columns
+        .iter()
+        .map(|(name, _)| format!("\"{}\"", name))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let insert_sql = format!(
+        "INSERT INTO \"{}\" ({}) VALUES ({})",
+        table_name, column_names, placeholders
+    );
+
+    connection
+        .prepare(&insert_sql)
+        .context("Failed to prepare insert statement")
+}
+
+/// Actually bind the CSV record's fields into the prepared statement
+/// and execute the insertion for a single record.
+pub fn insert_record<'a>(
+    statement: &mut Statement<'a>,
+    record: &csv::StringRecord,
+) -> Result<(), DatapumpError> {
+    // Bind each field
+    for (i, field) in record.iter().enumerate() {
+        if field.is_empty() {
+            statement.bind((i + 1, ()))?;
+        } else {
+            statement.bind((i + 1, field))?;
+        }
+    }
+
+    // Execute
+    statement.next()?;
+
+    // Reset for the next row
+    statement.reset()?;
+
+    Ok(())
+}
+
+/// Bind a typed record into the prepared statement and execute the insertion.
+pub fn insert_values<'a>(
+    statement: &mut Statement<'a>,
+    values: &[Value],
+) -> Result<(), DatapumpError> {
+    for (i, value) in values.iter().enumerate() {
+        let index = i + 1;
+        match value {
+            Value::Null => statement.bind((index, ())),
+            Value::Integer(n) => statement.bind((index, *n)),
+            Value::Real(f) => statement.bind((index, *f)),
+            Value::Text(s) => statement.bind((index, s.as_str())),
+            Value::Boolean(b) => statement.bind((index, *b as i64)),
+            // Dates and times are stored as ISO 8601 text
+            other => statement.bind((index, other.to_string().as_str())),
+        }?;
+    }
+
+    statement.next()?;
+    statement.reset()?;
+
+    Ok(())
+}
+
+/// Helper to execute a closure with up to 5 retries on "database is locked" errors.
+fn execute_with_retry<F>(connection: &Connection, f: F) -> Result<(), DatapumpError>
+where
+    F: Fn(&Connection) -> Result<(), DatapumpError>,
+{
+    let mut retries = 5;
+    loop {
+        match f(connection) {
+            Ok(_) => return Ok(()),
+            Err(e) if e.to_string().contains("database is locked") && retries > 0 => {
+                retries -= 1;
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Start a transaction immediately.
+pub fn begin_transaction(connection: &Connection) -> Result<(), DatapumpError> {
+    execute_with_retry(connection, |conn| {
+        conn.execute("BEGIN IMMEDIATE TRANSACTION")
+            .context("Failed to start transaction")
+    })
+}
+
+/// Commit the current transaction.
+pub fn commit_transaction(connection: &Connection) -> Result<(), DatapumpError> {
+    connection.execute("COMMIT").context("Failed to COMMIT")
+}
+
+/// Rollback the current transaction (used on error or cancellation).
+pub fn rollback_transaction(connection: &Connection) -> Result<(), DatapumpError> {
+    connection.execute("ROLLBACK").context("Failed to ROLLBACK")
+}
+
+/// Commit the current transaction, then immediately start a new one.
+/// Used for batch commits.
+pub fn commit_and_begin_new_transaction(connection: &Connection) -> Result<(), DatapumpError> {
+    execute_with_retry(connection, |conn| {
+        conn.execute("COMMIT")?;
+        conn.execute("BEGIN IMMEDIATE TRANSACTION")
+            .map_err(DatapumpError::from)
+    })
+}
@@ -0,0 +1,611 @@
+// src/types.rs
+
+use crate::source::Value;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// Date formats recognised, in the order they are tried. Month-first comes
+/// before day-first, so dates that fit both are read the US way unless a
+/// day past the 12th rules it out.
+pub const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%m/%d/%Y",
+    "%d/%m/%Y",
+    "%d.%m.%Y",
+    "%d-%m-%Y",
+    "%m/%d/%y",
+    "%d/%m/%y",
+    "%d.%m.%y",
+    "%d %b %Y",
+    "%d-%b-%Y",
+    "%b %d %Y",
+    "%b %d, %Y",
+];
+
+/// Formats of a date and time of day without a UTC offset. `%.f` also
+/// matches no fraction at all.
+pub const TIMESTAMP_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%m/%d/%Y %H:%M:%S%.f",
+    "%d/%m/%Y %H:%M:%S%.f",
+    "%d.%m.%Y %H:%M:%S%.f",
+    "%m/%d/%Y %H:%M",
+    "%d/%m/%Y %H:%M",
+    "%d.%m.%Y %H:%M",
+    "%m/%d/%Y %I:%M:%S %p",
+    "%m/%d/%Y %I:%M %p",
+];
+
+/// Formats of a date and time with a UTC offset. `%#z` matches "Z",
+/// "+02", "+0200" and "+02:00".
+pub const TIMESTAMPTZ_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f%#z",
+    "%Y-%m-%d %H:%M:%S%.f%#z",
+    "%Y-%m-%d %H:%M:%S%.f %#z",
+];
+
+pub const TIME_FORMATS: &[&str] = &["%H:%M:%S%.f", "%H:%M", "%I:%M:%S %p", "%I:%M %p"];
+
+/// Number formats recognised, each written as 1234.56 would be in it, in
+/// the order they are preferred when values fit several.
+pub const NUMBER_FORMATS: &[&str] = &[
+    "1234.56", "1,234.56", "1.234,56", "1 234,56", "1'234.56", "1234,56",
+];
+
+/// Currency symbols ignored before or after a number.
+const CURRENCY_SYMBOLS: &[char] = &[
+    '$', '€', '£', '¥', '₹', '₩', '₽', '₺', '₪', '₫', '฿', '₴', '₦', '¢',
+];
+
+/// What a column's values are loaded as, from a type name in a schema such
+/// as "integer" or "date(%d/%m/%Y)". Dates and times carry the format their
+/// values are written in; without one, each value is read with the first of
+/// the known formats that fits it. Numbers may carry one of
+/// `NUMBER_FORMATS`, as in "real(1.234,56)"; without one, they are read as
+/// plain numbers only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer(Option<String>),
+    Real(Option<String>),
+    Text,
+    Boolean,
+    Date(Option<String>),
+    Time(Option<String>),
+    Timestamp(Option<String>),
+    /// A timestamp with a UTC offset.
+    TimestampTz(Option<String>),
+    Uuid,
+    Json,
+    /// Any other type name, such as "varchar(20)": the column is created with
+    /// it and its values are loaded as text for the destination to convert.
+    Other(String),
+}
+
+impl ColumnType {
+    pub fn parse(name: &str) -> ColumnType {
+        let name = name.trim();
+        let (base, format) = match name.split_once('(') {
+            Some((base, rest)) if rest.ends_with(')') => {
+                (base.trim(), Some(rest[..rest.len() - 1].to_string()))
+            }
+            _ => (name, None),
+        };
+        match (base.to_lowercase().as_str(), format) {
+            ("integer", format) => ColumnType::Integer(format),
+            ("real" | "number", format) => ColumnType::Real(format),
+            ("text", None) => ColumnType::Text,
+            ("boolean" | "bool", None) => ColumnType::Boolean,
+            ("date", format) => ColumnType::Date(format),
+            ("time", format) => ColumnType::Time(format),
+            ("timestamp", format) => ColumnType::Timestamp(format),
+            ("timestamptz", format) => ColumnType::TimestampTz(format),
+            ("uuid", None) => ColumnType::Uuid,
+            ("json", None) => ColumnType::Json,
+            _ => ColumnType::Other(name.to_string()),
+        }
+    }
+
+    /// The type name as written in a schema; `parse` reads it back.
+    pub fn name(&self) -> String {
+        let with_format = |base: &str, format: &Option<String>| match format {
+            Some(format) => format!("{}({})", base, format),
+            None => base.to_string(),
+        };
+        match self {
+            ColumnType::Integer(format) => with_format("integer", format),
+            ColumnType::Real(format) => with_format("real", format),
+            ColumnType::Text => "text".to_string(),
+            ColumnType::Boolean => "boolean".to_string(),
+            ColumnType::Date(format) => with_format("date", format),
+            ColumnType::Time(format) => with_format("time", format),
+            ColumnType::Timestamp(format) => with_format("timestamp", format),
+            ColumnType::TimestampTz(format) => with_format("timestamptz", format),
+            ColumnType::Uuid => "uuid".to_string(),
+            ColumnType::Json => "json".to_string(),
+            ColumnType::Other(name) => name.clone(),
+        }
+    }
+
+    /// The type name without its format.
+    pub fn base_name(&self) -> String {
+        match self {
+            ColumnType::Integer(_) => "integer".to_string(),
+            ColumnType::Real(_) => "real".to_string(),
+            ColumnType::Date(_) => "date".to_string(),
+            ColumnType::Time(_) => "time".to_string(),
+            ColumnType::Timestamp(_) => "timestamp".to_string(),
+            ColumnType::TimestampTz(_) => "timestamptz".to_string(),
+            other => other.name(),
+        }
+    }
+
+    /// The format of a number, date or time type's values, if it has one.
+    pub fn format(&self) -> Option<&str> {
+        match self {
+            ColumnType::Integer(format)
+            | ColumnType::Real(format)
+            | ColumnType::Date(format)
+            | ColumnType::Time(format)
+            | ColumnType::Timestamp(format)
+            | ColumnType::TimestampTz(format) => format.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether values of the type can be given a format: numbers, dates and
+    /// times.
+    pub fn takes_format(&self) -> bool {
+        matches!(
+            self,
+            ColumnType::Integer(_)
+                | ColumnType::Real(_)
+                | ColumnType::Date(_)
+                | ColumnType::Time(_)
+                | ColumnType::Timestamp(_)
+                | ColumnType::TimestampTz(_)
+        )
+    }
+
+    /// The type with `format` as the format of its values, if it takes one
+    /// and `format` is given.
+    pub fn with_format(mut self, format: Option<String>) -> ColumnType {
+        if let ColumnType::Integer(current)
+        | ColumnType::Real(current)
+        | ColumnType::Date(current)
+        | ColumnType::Time(current)
+        | ColumnType::Timestamp(current)
+        | ColumnType::TimestampTz(current) = &mut self
+        {
+            if format.is_some() {
+                *current = format;
+            }
+        }
+        self
+    }
+
+    /// The type a SQLite column is created with. SQLite keeps dates and
+    /// times as ISO 8601 text and booleans as 0 or 1.
+    pub fn sqlite_type(&self) -> String {
+        self.base_name()
+    }
+
+    /// The type a PostgreSQL column is created with: 64-bit numbers, as the
+    /// values are parsed, and the other types by their own names.
+    pub fn postgres_type(&self) -> String {
+        match self {
+            ColumnType::Integer(_) => "bigint".to_string(),
+            ColumnType::Real(_) => "double precision".to_string(),
+            ColumnType::Json => "jsonb".to_string(),
+            other => other.base_name(),
+        }
+    }
+
+    /// Convert a raw text field into a value of this type. Empty fields are
+    /// NULL. Values that don't parse as the type are kept as text so the
+    /// destination can decide what to do with them.
+    pub fn value(&self, field: &str) -> Value {
+        if field.is_empty() {
+            return Value::Null;
+        }
+        let value = match self {
+            ColumnType::Integer(format) => {
+                parse_integer(field, format.as_deref()).map(Value::Integer)
+            }
+            ColumnType::Real(format) => parse_real(field, format.as_deref()).map(Value::Real),
+            ColumnType::Boolean => parse_bool(field).map(Value::Boolean),
+            ColumnType::Date(format) => parse_date(field, format.as_deref()).map(Value::Date),
+            ColumnType::Time(format) => parse_time(field, format.as_deref()).map(Value::Time),
+            ColumnType::Timestamp(format) => {
+                parse_timestamp(field, format.as_deref()).map(Value::Timestamp)
+            }
+            ColumnType::TimestampTz(format) => {
+                parse_timestamptz(field, format.as_deref()).map(Value::TimestampTz)
+            }
+            ColumnType::Text | ColumnType::Uuid | ColumnType::Json | ColumnType::Other(_) => None,
+        };
+        value.unwrap_or_else(|| Value::Text(field.to_string()))
+    }
+}
+
+/// true/false, yes/no, y/n, t/f and on/off in any case, and 1/0.
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "t" | "yes" | "y" | "on" | "1" => Some(true),
+        "false" | "f" | "no" | "n" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether `value` spells out a boolean, as opposed to 1 or 0, which are as
+/// likely to be numbers.
+pub fn is_bool_word(value: &str) -> bool {
+    !matches!(value.trim(), "1" | "0" | "on" | "off") && parse_bool(value).is_some()
+}
+
+/// A UUID in its usual hyphenated form.
+pub fn is_uuid(value: &str) -> bool {
+    value.len() == 36 && uuid::Uuid::try_parse(value).is_ok()
+}
+
+/// A JSON object or array.
+pub fn is_json(value: &str) -> bool {
+    let value = value.trim();
+    let bracketed = (value.starts_with('{') && value.ends_with('}'))
+        || (value.starts_with('[') && value.ends_with(']'));
+    bracketed && serde_json::from_str::<serde::de::IgnoredAny>(value).is_ok()
+}
+
+/// Without a format, only a plain integer such as "-1234". In one of
+/// `NUMBER_FORMATS`, anything `parse_real` reads that is a whole number.
+pub fn parse_integer(value: &str, format: Option<&str>) -> Option<i64> {
+    match format {
+        None => value.parse().ok(),
+        Some(format) => {
+            let number = Number::parse(value, format)?;
+            if number.percent {
+                let real = number.real()?;
+                (real.fract() == 0.0 && real.abs() < i64::MAX as f64).then_some(real as i64)
+            } else if number.fraction.bytes().all(|b| b == b'0') {
+                let integer: i64 = number.integer.parse().ok()?;
+                Some(if number.negative { -integer } else { integer })
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Without a format, only a plain number such as "-1234.56" or "1e6". In
+/// one of `NUMBER_FORMATS`, the number may be grouped by thousands, have a
+/// currency symbol or code before or after it, be a percentage, which is
+/// divided by 100, and be negative by a minus sign or, as accountants
+/// write it, by parentheses: "(1,234.56 €)".
+pub fn parse_real(value: &str, format: Option<&str>) -> Option<f64> {
+    match format {
+        None => value.parse().ok(),
+        Some(format) => Number::parse(value, format)?.real(),
+    }
+}
+
+/// The decimal separator and thousands separator, if any, of a number
+/// format written as 1234.56 would be in it.
+pub fn number_separators(format: &str) -> Option<(char, Option<char>)> {
+    let rest = format.strip_prefix('1')?;
+    let (grouping, rest) = match rest.strip_prefix("234") {
+        Some(rest) => (None, rest),
+        None => {
+            let mut chars = rest.chars();
+            let grouping = chars.next()?;
+            (Some(grouping), chars.as_str().strip_prefix("234")?)
+        }
+    };
+    let decimal = rest.strip_suffix("56")?;
+    let mut chars = decimal.chars();
+    match (chars.next(), chars.next()) {
+        (Some(decimal), None) if Some(decimal) != grouping => Some((decimal, grouping)),
+        _ => None,
+    }
+}
+
+/// A number as written in a number format, taken apart.
+struct Number<'a> {
+    negative: bool,
+    percent: bool,
+    /// Digits before the decimal separator, without thousands separators.
+    integer: String,
+    /// Digits after the decimal separator.
+    fraction: &'a str,
+}
+
+impl<'a> Number<'a> {
+    fn parse(value: &'a str, format: &str) -> Option<Number<'a>> {
+        let (decimal, grouping) = number_separators(format)?;
+        let mut text = value.trim();
+        let mut negative = false;
+        if let Some(inner) = text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+            negative = true;
+            text = inner.trim();
+        }
+        let percent = match text.strip_suffix('%') {
+            Some(rest) => {
+                text = rest.trim_end();
+                true
+            }
+            None => false,
+        };
+        text = strip_currency(text);
+        if let Some(rest) = text.strip_prefix('-') {
+            if negative {
+                return None;
+            }
+            negative = true;
+            text = strip_currency(rest);
+        } else if let Some(rest) = text.strip_prefix('+') {
+            text = strip_currency(rest);
+        }
+
+        let (integer, fraction) = match text.split_once(decimal) {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (text, ""),
+        };
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if integer.len() + fraction.len() == 0 || !all_digits(fraction) {
+            return None;
+        }
+        let integer = match grouping {
+            Some(grouping) if integer.contains(|c| is_separator(c, grouping)) => {
+                let groups: Vec<&str> = integer.split(|c| is_separator(c, grouping)).collect();
+                let first_ok = (1..=3).contains(&groups[0].len());
+                if !first_ok || groups[1..].iter().any(|group| group.len() != 3) {
+                    return None;
+                }
+                groups.concat()
+            }
+            _ => integer.to_string(),
+        };
+        if !all_digits(&integer) {
+            return None;
+        }
+        Some(Number {
+            negative,
+            percent,
+            integer,
+            fraction,
+        })
+    }
+
+    fn real(&self) -> Option<f64> {
+        let sign = if self.negative { "-" } else { "" };
+        let integer = if self.integer.is_empty() {
+            "0"
+        } else {
+            &self.integer
+        };
+        let real: f64 = format!("{}{}.{}", sign, integer, self.fraction)
+            .parse()
+            .ok()?;
+        Some(if self.percent { real / 100.0 } else { real })
+    }
+}
+
+/// Whether `c` separates thousands where `grouping` does. A space also
+/// stands for the no-break spaces some locales group with.
+fn is_separator(c: char, grouping: char) -> bool {
+    c == grouping || (grouping == ' ' && matches!(c, '\u{a0}' | '\u{202f}'))
+}
+
+/// `text` without a currency symbol, or a code such as "EUR" set apart by a
+/// space, before or after it.
+fn strip_currency(text: &str) -> &str {
+    let is_code = |code: &str| code.len() == 3 && code.bytes().all(|b| b.is_ascii_uppercase());
+    let text = text
+        .trim_start_matches(CURRENCY_SYMBOLS)
+        .trim_end_matches(CURRENCY_SYMBOLS)
+        .trim();
+    let text = match text.split_once(' ') {
+        Some((code, rest)) if is_code(code) => rest,
+        _ => text,
+    };
+    match text.rsplit_once(' ') {
+        Some((rest, code)) if is_code(code) => rest,
+        _ => text,
+    }
+    .trim()
+}
+
+/// Parse `value` in `format`, or in the first of `formats` that fits.
+fn parse_with<T>(
+    value: &str,
+    format: Option<&str>,
+    formats: &[&str],
+    parse: impl Fn(&str, &str) -> Option<T>,
+) -> Option<T> {
+    let value = value.trim();
+    match format {
+        Some(format) => parse(value, format),
+        None => formats.iter().find_map(|format| parse(value, format)),
+    }
+}
+
+/// Without a format, a timestamp is also taken for its date.
+pub fn parse_date(value: &str, format: Option<&str>) -> Option<NaiveDate> {
+    parse_with(value, format, DATE_FORMATS, |value, format| {
+        NaiveDate::parse_from_str(value, format)
+            .ok()
+            .filter(plausible)
+    })
+    .or_else(|| {
+        format
+            .is_none()
+            .then(|| parse_timestamp(value, None).map(|timestamp| timestamp.date()))
+            .flatten()
+    })
+}
+
+pub fn parse_time(value: &str, format: Option<&str>) -> Option<NaiveTime> {
+    parse_with(value, format, TIME_FORMATS, |value, format| {
+        NaiveTime::parse_from_str(value, format).ok()
+    })
+}
+
+pub fn parse_timestamp(value: &str, format: Option<&str>) -> Option<NaiveDateTime> {
+    parse_with(value, format, TIMESTAMP_FORMATS, |value, format| {
+        NaiveDateTime::parse_from_str(value, format)
+            .ok()
+            .filter(|timestamp| plausible(&timestamp.date()))
+    })
+}
+
+pub fn parse_timestamptz(value: &str, format: Option<&str>) -> Option<DateTime<FixedOffset>> {
+    parse_with(value, format, TIMESTAMPTZ_FORMATS, |value, format| {
+        DateTime::parse_from_str(value, format)
+            .ok()
+            .filter(|timestamp| plausible(&timestamp.date_naive()))
+    })
+}
+
+/// A four-digit year, which rules out "02/03/24" read as the year 24.
+fn plausible(date: &NaiveDate) -> bool {
+    (1000..=9999).contains(&date.year())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINT: &str = "1,234.56";
+    const COMMA: &str = "1.234,56";
+
+    fn real(value: &str, format: &str) -> Option<f64> {
+        Number::parse(value, format)?.real()
+    }
+
+    #[test]
+    fn number_with_grouping_and_decimal_comma() {
+        assert_eq!(real("1.234,56", COMMA), Some(1234.56));
+        // A '.' decimal leaves ",56" after the point, which is no fraction
+        assert_eq!(real("1.234,56", POINT), None);
+    }
+
+    #[test]
+    fn number_in_parentheses_is_negative() {
+        assert_eq!(real("(1,234)", POINT), Some(-1234.0));
+        assert_eq!(parse_integer("(1,234)", Some(POINT)), Some(-1234));
+        assert_eq!(real("(1,234)", COMMA), Some(-1.234));
+        assert_eq!(parse_integer("(1,234)", Some(COMMA)), None);
+    }
+
+    #[test]
+    fn percentage_is_divided_by_100() {
+        assert_eq!(real("12%", POINT), Some(0.12));
+        assert_eq!(real("12%", COMMA), Some(0.12));
+        assert_eq!(parse_integer("12%", Some(POINT)), None);
+        assert_eq!(parse_integer("1200%", Some(COMMA)), Some(12));
+    }
+
+    #[test]
+    fn currency_symbol_is_ignored() {
+        assert_eq!(real("$1,000", POINT), Some(1000.0));
+        assert_eq!(parse_integer("$1,000", Some(POINT)), Some(1000));
+        assert_eq!(real("$1,000", COMMA), Some(1.0));
+        assert_eq!(parse_integer("$1,000", Some(COMMA)), Some(1));
+    }
+
+    #[test]
+    fn currency_code_and_sign() {
+        assert_eq!(real("EUR 1.234,56", COMMA), Some(1234.56));
+        assert_eq!(real("-1 234,56 €", "1 234,56"), Some(-1234.56));
+        assert_eq!(real("1\u{a0}234,56", "1 234,56"), Some(1234.56));
+        assert_eq!(real("(-1,234)", POINT), None);
+    }
+
+    #[test]
+    fn misplaced_grouping_is_rejected() {
+        assert_eq!(real("12,34", POINT), None);
+        assert_eq!(real("1234,567", POINT), None);
+        assert_eq!(real("1,234,567.8", POINT), Some(1234567.8));
+    }
+
+    #[test]
+    fn every_number_format_reads_its_own_example() {
+        for format in NUMBER_FORMATS {
+            assert!(number_separators(format).is_some(), "{}", format);
+            assert_eq!(real(format, format), Some(1234.56), "{}", format);
+        }
+        assert_eq!(number_separators("1.234.56"), None);
+        assert_eq!(number_separators("12.34"), None);
+    }
+
+    #[test]
+    fn every_date_format_reads_a_date_past_the_12th() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 25).unwrap();
+        for format in DATE_FORMATS {
+            let written = date.format(format).to_string();
+            assert_eq!(parse_date(&written, Some(format)), Some(date), "{}", format);
+            assert_eq!(parse_date(&written, None), Some(date), "{}", written);
+        }
+    }
+
+    #[test]
+    fn ambiguous_date_is_read_month_first() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        assert_eq!(parse_date("03/04/2024", None), date(2024, 3, 4));
+        assert_eq!(parse_date("13/04/2024", None), date(2024, 4, 13));
+        assert_eq!(parse_date("02/03/24", None), date(2024, 2, 3));
+        assert_eq!(parse_date("2024-03-04 05:06:07", None), date(2024, 3, 4));
+        assert_eq!(parse_date("02/03/24", Some("%d/%m/%Y")), None);
+    }
+
+    #[test]
+    fn every_timestamp_format_reads_a_timestamp() {
+        let timestamp = NaiveDate::from_ymd_opt(2024, 3, 25)
+            .unwrap()
+            .and_hms_opt(17, 6, 0)
+            .unwrap();
+        for format in TIMESTAMP_FORMATS {
+            let written = timestamp.format(format).to_string();
+            assert_eq!(
+                parse_timestamp(&written, None),
+                Some(timestamp),
+                "{}",
+                written
+            );
+        }
+        let fraction = parse_timestamp("2024-03-25T17:06:00.250", None).unwrap();
+        assert_eq!(fraction.and_utc().timestamp_subsec_millis(), 250);
+    }
+
+    #[test]
+    fn every_timestamptz_offset_form_is_read() {
+        let expected = DateTime::parse_from_rfc3339("2024-03-25T17:06:00+02:00").unwrap();
+        for written in [
+            "2024-03-25T17:06:00+02:00",
+            "2024-03-25T17:06:00+0200",
+            "2024-03-25 17:06:00+02",
+            "2024-03-25 17:06:00 +02:00",
+            "2024-03-25T15:06:00Z",
+        ] {
+            assert_eq!(
+                parse_timestamptz(written, None),
+                Some(expected),
+                "{}",
+                written
+            );
+        }
+        assert_eq!(parse_timestamptz("2024-03-25 17:06:00", None), None);
+    }
+
+    #[test]
+    fn every_time_format_reads_a_time() {
+        let time = NaiveTime::from_hms_opt(17, 6, 0).unwrap();
+        for format in TIME_FORMATS {
+            let written = time.format(format).to_string();
+            assert_eq!(parse_time(&written, None), Some(time), "{}", written);
+        }
+        assert_eq!(parse_time("5:06 pm", None), Some(time));
+    }
+}
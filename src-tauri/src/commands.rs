@@ -1,476 +1,433 @@
 // src/commands.rs
 
-use crate::csv_reader; // new module for CSV reading
-use crate::csv_schema; // your existing csv_schema module
-use crate::postgres_writer; // new module for PostgreSQL writing
-use bytes::BytesMut;
-use crate::sqlite_writer; // new module for SQLite writing
-use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::sync::OnceLock;
+use datapump_core::archive::{self, ArchiveEntry}; // zip archives read in place
+use datapump_core::checkpoint::{self, Checkpoint}; // resumable jobs
+use datapump_core::constraints::Constraints; // NOT NULL and primary keys of new tables
+use datapump_core::csv_schema::{self, CsvSchema, Sample};
+use datapump_core::dialect::{Dialect, DialectOptions}; // how a delimited file is written
+use datapump_core::encoding::{self, DecodedFile}; // input files in any encoding
+use datapump_core::error::{Context, DatapumpError};
+use datapump_core::files; // several files loaded as one
+use datapump_core::fixed_width::{self, FixedWidthColumn, FixedWidthLayout}; // fixed-width text files
+use datapump_core::headers::{self, ColumnName, HeaderPolicy};
+use datapump_core::input::CsvInput;
+use datapump_core::jobs::{self, JobStatus}; // per-migration cancellation and status
+use datapump_core::migrate::{
+    import_archive, resume_migration as resume_from_checkpoint, run_migration, LoadMode,
+    MemberImport, SinkConfig, SourceConfig,
+}; // generic source -> sink migration
+use datapump_core::progress::{ProgressEvent, ProgressSink, Stage};
+use datapump_core::ragged::RaggedRowPolicy; // rows with too few or too many fields
+use datapump_core::schema::Schema; // columns as get_csv_schema infers them
+use datapump_core::spec::JobSpec; // declarative, repeatable migrations
 use tauri::Emitter;
 
-// This static remains here so we can set/cancel across the entire operation.
-static CANCELLATION_REQUESTED: OnceLock<AtomicBool> = OnceLock::new();
+/// Forwards engine progress events to the webview.
+pub struct WindowProgress(pub tauri::Window);
 
-pub fn is_cancellation_requested() -> bool {
-    CANCELLATION_REQUESTED
-        .get_or_init(|| AtomicBool::new(false))
-        .load(Ordering::SeqCst)
+impl ProgressSink for WindowProgress {
+    fn report(&self, event: ProgressEvent) {
+        let _ = self.0.emit("migration_progress", event);
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn cancel_migration(job_id: String) -> Result<(), DatapumpError> {
+    let job = jobs::registry()
+        .get(&job_id)
+        .ok_or_else(|| DatapumpError::invalid(format!("Unknown job: {}", job_id)))?;
+    job.cancel();
+    Ok(())
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ProgressEvent {
-    pub total_rows: usize,
-    pub processed_rows: usize,
-    pub row_count: usize,
-    pub batch_size: usize,
-    pub status: String,
-    pub message: Option<String>,
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_job_status(job_id: String) -> Result<JobStatus, DatapumpError> {
+    jobs::registry()
+        .get(&job_id)
+        .map(|job| job.status())
+        .ok_or_else(|| DatapumpError::invalid(format!("Unknown job: {}", job_id)))
 }
 
 #[tauri::command]
-pub async fn cancel_migration() -> Result<(), String> {
-    let flag = CANCELLATION_REQUESTED.get_or_init(|| AtomicBool::new(false));
-    flag.store(true, Ordering::SeqCst);
+pub async fn list_jobs() -> Result<Vec<JobStatus>, DatapumpError> {
+    Ok(jobs::registry().list())
+}
+
+/// Pause a running migration once the batch in flight is committed.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn pause_migration(job_id: String) -> Result<(), DatapumpError> {
+    let job = jobs::registry()
+        .get(&job_id)
+        .filter(|job| job.is_running())
+        .ok_or_else(|| DatapumpError::invalid(format!("No running job: {}", job_id)))?;
+    job.pause();
     Ok(())
 }
 
+/// Resume a paused migration, or continue an interrupted one from its last
+/// checkpoint. A paused job carries on in the command that started it, so
+/// this returns straight away; otherwise it runs until the load finishes.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn resume_migration(window: tauri::Window, job_id: String) -> Result<(), DatapumpError> {
+    if let Some(job) = jobs::registry()
+        .get(&job_id)
+        .filter(|job| job.is_running() && job.is_paused())
+    {
+        job.resume();
+        return Ok(());
+    }
+    resume_from_checkpoint(&WindowProgress(window), &job_id)
+        .await
+        .map(|_| ())
+}
+
+/// Interrupted migrations that can be resumed, most recent first.
 #[tauri::command]
-pub async fn reset_cancellation() -> Result<(), String> {
-    let flag = CANCELLATION_REQUESTED.get_or_init(|| AtomicBool::new(false));
-    flag.store(false, Ordering::SeqCst);
-    Ok(())
+pub async fn list_checkpoints() -> Result<Vec<Checkpoint>, DatapumpError> {
+    Ok(checkpoint::list())
+}
+
+/// Sniff how a delimited file is written: its encoding, delimiter, quoting,
+/// line endings, whether it has a header row and where that row starts.
+/// `options` fixes whatever should not be detected.
+#[tauri::command]
+pub async fn sniff_dialect(
+    filePath: String,
+    options: Option<DialectOptions>,
+) -> Result<Dialect, DatapumpError> {
+    Dialect::sniff(&filePath, &options.unwrap_or_default())
+}
+
+/// Turn header names into the safe, unique column names a migration creates.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn normalize_headers(headers: Vec<String>, snake_case: Option<bool>) -> Vec<ColumnName> {
+    let policy = HeaderPolicy {
+        snake_case: snake_case.unwrap_or(false),
+    };
+    headers::normalize(headers.iter().map(String::as_str), policy)
 }
 
+/// Infer a CSV file's schema, read with the dialect sniffed from it. `options`
+/// overrides detection, e.g. of the encoding or header row, and `snakeCase`
+/// rewrites header names; the result maps every header to its column name.
+/// Column types are inferred from the rows `sample` picks, the first 1000
+/// by default. For a glob pattern, the first file it matches is read.
 #[tauri::command]
-pub async fn get_csv_schema(window: tauri::Window, filePath: String) -> Result<String, String> {
+pub async fn get_csv_schema(
+    window: tauri::Window,
+    filePath: String,
+    options: Option<DialectOptions>,
+    snakeCase: Option<bool>,
+    sample: Option<Sample>,
+) -> Result<CsvSchema, DatapumpError> {
+    let progress = WindowProgress(window);
+
     // Emit event before schema parsing
-    let _ = window.emit(
-        "migration_progress",
-        ProgressEvent {
-            total_rows: 0,
-            processed_rows: 0,
-            row_count: 0,
-            batch_size: 0,
-            status: "parsing_schema_start".to_string(),
-            message: Some("Starting schema parsing".to_string()),
-        },
-    );
+    progress.report(ProgressEvent::new(
+        Stage::Sniffing,
+        Some("Starting schema parsing".to_string()),
+    ));
 
     // Delegate to csv_schema module
-    let result = csv_schema::get_csv_schema(&filePath);
+    let policy = HeaderPolicy {
+        snake_case: snakeCase.unwrap_or(false),
+    };
+    let result = files::expand(&[filePath]).and_then(|files| {
+        let dialect = Dialect::sniff(&files[0], &options.unwrap_or_default())?;
+        csv_schema::get_csv_schema(&files[0], dialect, policy, sample.unwrap_or_default())
+    });
 
     // Emit event after schema parsing
-    let _ = window.emit(
-        "migration_progress",
-        ProgressEvent {
-            total_rows: 0,
-            processed_rows: 0,
-            row_count: 0,
-            batch_size: 0,
-            status: "parsing_schema_complete".to_string(),
-            message: Some("Schema parsing complete".to_string()),
-        },
-    );
+    let message = match &result {
+        Ok(schema) => format!(
+            "Inferred column types from {} rows ({})",
+            schema.rows_sampled, schema.sample
+        ),
+        Err(_) => "Schema parsing complete".to_string(),
+    };
+    progress.report(ProgressEvent::new(Stage::Sniffing, Some(message)));
 
     result
 }
 
-#[tauri::command]
-pub async fn csv_to_postgres(
+/// Work out the columns of a fixed-width file, for a `fixed_width` source of
+/// `migrate`. `columns`, or `columnsFile`, gives them, with missing types
+/// inferred; with neither they are detected from the file's whitespace.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn detect_fixed_width(
+    file_path: String,
+    columns: Option<Vec<FixedWidthColumn>>,
+    columns_file: Option<String>,
+    options: Option<DialectOptions>,
+    snake_case: Option<bool>,
+) -> Result<FixedWidthLayout, DatapumpError> {
+    let policy = HeaderPolicy {
+        snake_case: snake_case.unwrap_or(false),
+    };
+    tokio::task::spawn_blocking(move || {
+        let columns = match columns_file {
+            Some(columns_file) => fixed_width::load_columns(&columns_file)?,
+            None => columns.unwrap_or_default(),
+        };
+        fixed_width::layout(&file_path, &columns, &options.unwrap_or_default(), policy)
+    })
+    .await?
+}
+
+/// Migrate any supported source into any supported destination.
+///
+/// `schema` is a `Schema` as `get_csv_schema` returns it; when omitted it is
+/// inferred from the source. Ragged rows fail the job unless `ragged` says otherwise.
+/// A new table gets `constraints`, e.g. those `get_csv_schema` suggested.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn migrate(
     window: tauri::Window,
-    filePath: String,
+    source: SourceConfig,
+    destination: SinkConfig,
+    table_name: String,
     batch_size: usize,
-    schema: String,
-    dbPath: String,
-    tableName: String,
-) -> Result<(), String> {
-    // 1. Validate input parameters
-    if !std::path::Path::new(&filePath).exists() {
-        return Err(format!("File does not exist: {}", filePath));
-    }
-    if batch_size == 0 {
-        return Err("Batch size must be greater than 0".to_string());
-    }
-    if schema.is_empty() {
-        return Err("Schema cannot be empty".to_string());
-    }
-    if tableName.is_empty() {
-        return Err("Table name cannot be empty".to_string());
-    }
+    schema: Option<Schema>,
+    mode: Option<LoadMode>,
+    ragged: Option<RaggedRowPolicy>,
+    constraints: Option<Constraints>,
+    job_id: Option<String>,
+) -> Result<usize, DatapumpError> {
+    let mut spec = JobSpec::new(source, destination, table_name);
+    spec.batch_size = batch_size;
+    spec.schema = schema;
+    spec.mode = mode.unwrap_or_default();
+    spec.ragged = ragged.unwrap_or_default();
+    spec.constraints = constraints.unwrap_or_default();
+
+    run_migration(&WindowProgress(window), &spec, job_id).await
+}
 
-    // 2. Parse the schema string into column definitions
-    let columns: Vec<(String, String)> = schema
-        .split(',')
-        .map(|s| {
-            let parts: Vec<&str> = s.split(':').collect();
-            if parts.len() != 2 {
-                return Err(format!(
-                    "Invalid schema format: expected 'name:type', got '{}'",
-                    s
-                ));
-            }
-            let name = parts[0].trim().to_string();
-            let typ = parts[1].trim().to_string();
-            if name.is_empty() || typ.is_empty() {
-                return Err(format!("Empty name or type in schema: '{}'", s));
-            }
-            Ok((name, typ))
-        })
-        .collect::<Result<Vec<_>, String>>()?;
-
-    // 3. Open PostgreSQL connection
-    let client = Arc::new(postgres_writer::open_connection(&dbPath).await?);
-
-    // 4. Create or ensure the table exists
-    postgres_writer::create_table(&client, &tableName, &columns).await?;
-
-    // 5. Begin COPY operation
-    let delimiter = csv_reader::detect_delimiter(&filePath)?;
-    let delimiter_str = if delimiter == b'\t' { "\t" } else { "," };
-    let mut copy_writer = postgres_writer::start_copy(&client, &tableName, &columns, delimiter_str).await?;
-
-    // 6. Count total rows (for progress reporting)
-    let _ = window.emit(
-        "migration_progress",
-        ProgressEvent {
-            total_rows: 0,
-            processed_rows: 0,
-            row_count: 0,
-            batch_size: 0,
-            status: "counting_rows".to_string(),
-            message: None,
-        },
-    );
-
-    let total_rows = csv_reader::count_rows(&filePath)?;
-    let _ = window.emit(
-        "migration_progress",
-        ProgressEvent {
-            total_rows,
-            processed_rows: 0,
-            row_count: 0,
-            batch_size: 0,
-            status: "counted_rows".to_string(),
-            message: None,
-        },
-    );
+/// List the files in a zip archive, each with the table it would be loaded
+/// into and, if it is in a supported format, its sniffed schema.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_zip_entries(
+    path: String,
+    options: Option<DialectOptions>,
+    snake_case: Option<bool>,
+) -> Result<Vec<ArchiveEntry>, DatapumpError> {
+    let policy = HeaderPolicy {
+        snake_case: snake_case.unwrap_or(false),
+    };
+    tokio::task::spawn_blocking(move || {
+        archive::list_entries(&path, &options.unwrap_or_default(), policy)
+    })
+    .await?
+}
 
-    // 7. Detect delimiter and create a CSV reader
-    let mut rdr = csv_reader::create_csv_reader(&filePath, delimiter)?;
+/// Load files from a zip archive into one table each, streaming them out of
+/// the archive. `members` are names from `list_zip_entries`; all supported
+/// files are loaded when it is empty.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_zip(
+    window: tauri::Window,
+    path: String,
+    members: Vec<String>,
+    destination: SinkConfig,
+    options: Option<DialectOptions>,
+    snake_case: Option<bool>,
+    mode: Option<LoadMode>,
+    ragged: Option<RaggedRowPolicy>,
+) -> Result<Vec<MemberImport>, DatapumpError> {
+    let source = SourceConfig::Csv {
+        path,
+        dialect: options.unwrap_or_default(),
+        headers: HeaderPolicy {
+            snake_case: snake_case.unwrap_or(false),
+        },
+    };
+    import_archive(
+        &WindowProgress(window),
+        &source,
+        &destination,
+        &members,
+        mode.unwrap_or_default(),
+        10_000,
+        &ragged.unwrap_or_default(),
+    )
+    .await
+}
 
-    // Reset cancellation flag at the start of migration
-    if let Some(flag) = CANCELLATION_REQUESTED.get() {
-        flag.store(false, Ordering::SeqCst);
-    }
+/// Read a job spec from a `.toml` or `.json` file.
+#[tauri::command]
+pub async fn load_job_spec(path: String) -> Result<JobSpec, DatapumpError> {
+    JobSpec::load(&path)
+}
 
-    // 8. Process CSV rows using COPY
-    let mut processed_rows = 0;
-    let mut row_count = 0;
-    let mut last_logged = 0;
-
-    for result in rdr.records() {
-        // Check for user cancellation
-        if let Some(flag) = CANCELLATION_REQUESTED.get() {
-            if flag.load(Ordering::SeqCst) {
-                let _ = window.emit(
-                    "migration_progress",
-                    ProgressEvent {
-                        total_rows,
-                        processed_rows,
-                        row_count,
-                        batch_size: 0,
-                        status: "cancelled".to_string(),
-                        message: Some("Migration cancelled by user".to_string()),
-                    },
-                );
-                // Reset the cancellation flag
-                flag.store(false, Ordering::SeqCst);
-                return Ok(());
-            }
-        }
+/// Check a job spec without running it.
+#[tauri::command]
+pub async fn validate_job_spec(spec: JobSpec) -> Result<(), DatapumpError> {
+    spec.validate()?;
+    spec.resolve_columns().map(|_| ())
+}
 
-        row_count += 1;
-        processed_rows += 1;
-
-        // Log progress every 100,000 rows
-        if row_count - last_logged >= 100_000 {
-            last_logged = row_count;
-            let _ = window.emit(
-                "migration_progress",
-                ProgressEvent {
-                    total_rows,
-                    processed_rows,
-                    row_count,
-                    batch_size,
-                    status: "processing".to_string(),
-                    message: Some(format!(
-                        "Processed {} rows ({:.1}%)",
-                        processed_rows,
-                        (processed_rows as f64 / total_rows as f64) * 100.0
-                    )),
-                },
-            );
-        }
+/// Save a job spec (e.g. the current UI configuration) to a `.toml` or `.json` file.
+#[tauri::command]
+pub async fn save_job_spec(spec: JobSpec, path: String) -> Result<(), DatapumpError> {
+    spec.save(&path)
+}
 
-        let record = match result {
-            Ok(r) => r,
-            Err(e) => {
-                return Err(format!("Error processing row {}: {}", row_count, e));
-            }
-        };
+/// Run the migration described by a job spec.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn run_job_spec(
+    window: tauri::Window,
+    spec: JobSpec,
+    job_id: Option<String>,
+) -> Result<usize, DatapumpError> {
+    run_migration(&WindowProgress(window), &spec, job_id).await
+}
 
-        // Convert the record to a tab-separated string
-        let copy_line = record.iter()
-            .map(|field| {
-                if field.is_empty() {
-                    "\\N".to_string() // PostgreSQL NULL value
-                } else {
-                    // Quote and escape the field
-                    format!("\"{}\"", field.replace('"', "\"\""))
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\t");
-
-        // Write the record using the COPY protocol
-        postgres_writer::write_copy_row(&mut copy_writer, BytesMut::from(copy_line.as_bytes())).await?;
-    }
+/// The source of `csv_to_postgres` and `csv_to_sqlite`: `filePath` and any
+/// `filePaths`, each a file or a glob pattern, loaded into one table.
+fn csv_source(
+    file_path: String,
+    file_paths: Option<Vec<String>>,
+    dialect: Option<DialectOptions>,
+    source_file_column: Option<bool>,
+) -> SourceConfig {
+    let mut paths = vec![file_path];
+    paths.extend(file_paths.unwrap_or_default());
+    SourceConfig::csv_files(
+        paths,
+        dialect.unwrap_or_default(),
+        HeaderPolicy::default(),
+        source_file_column.unwrap_or(false),
+    )
+}
 
-    // Finish COPY operation
-    postgres_writer::finish_copy(copy_writer).await?;
-
-    // Final progress event
-    let _ = window.emit(
-        "migration_progress",
-        ProgressEvent {
-            total_rows,
-            processed_rows,
-            row_count: processed_rows,
-            batch_size: 0,
-            status: "complete".to_string(),
-            message: Some(format!("Successfully copied {} rows", processed_rows)),
+/// Load CSV/TSV files into a PostgreSQL table. `filePath` may be a glob
+/// pattern and `filePaths` names more files; all must have the same columns.
+/// `schema` is the `Schema` `get_csv_schema` returned, possibly edited.
+/// With `sourceFileColumn`, each row records its file in `_source_file`.
+/// `raggedRows` says what to do with rows with too few or too many fields,
+/// and a new table gets `constraints`.
+#[tauri::command]
+pub async fn csv_to_postgres(
+    window: tauri::Window,
+    filePath: String,
+    batch_size: usize,
+    schema: Schema,
+    dbPath: String,
+    tableName: String,
+    dialect: Option<DialectOptions>,
+    filePaths: Option<Vec<String>>,
+    sourceFileColumn: Option<bool>,
+    raggedRows: Option<RaggedRowPolicy>,
+    constraints: Option<Constraints>,
+    job_id: Option<String>,
+) -> Result<(), DatapumpError> {
+    migrate(
+        window,
+        csv_source(filePath, filePaths, dialect, sourceFileColumn),
+        SinkConfig::Postgres {
+            connection_string: dbPath,
         },
-    );
-
-    Ok(())
+        tableName,
+        batch_size,
+        Some(schema),
+        None,
+        raggedRows,
+        constraints,
+        job_id,
+    )
+    .await
+    .map(|_| ())
 }
 
+/// Load CSV/TSV files into a SQLite table, as `csv_to_postgres` does.
 #[tauri::command]
 pub async fn csv_to_sqlite(
     window: tauri::Window,
     filePath: String,
     batch_size: usize,
-    schema: String,
+    schema: Schema,
     dbPath: String,
     tableName: String,
-) -> Result<(), String> {
-    // 1. Validate input parameters
-    if !std::path::Path::new(&filePath).exists() {
-        return Err(format!("File does not exist: {}", filePath));
-    }
-    if batch_size == 0 {
-        return Err("Batch size must be greater than 0".to_string());
-    }
-    if schema.is_empty() {
-        return Err("Schema cannot be empty".to_string());
-    }
-    if tableName.is_empty() {
-        return Err("Table name cannot be empty".to_string());
-    }
-
-    // 2. Parse the schema string into column definitions
-    let columns: Vec<(String, String)> = schema
-        .split(',')
-        .map(|s| {
-            let parts: Vec<&str> = s.split(':').collect();
-            if parts.len() != 2 {
-                return Err(format!(
-                    "Invalid schema format: expected 'name:type', got '{}'",
-                    s
-                ));
-            }
-            let name = parts[0].trim().to_string();
-            let typ = parts[1].trim().to_string();
-            if name.is_empty() || typ.is_empty() {
-                return Err(format!("Empty name or type in schema: '{}'", s));
-            }
-            Ok((name, typ))
-        })
-        .collect::<Result<Vec<_>, String>>()?;
-
-    // 3. Open and configure the SQLite database
-    let connection = sqlite_writer::open_connection(&dbPath)?;
-
-    // 4. Create or ensure the table exists
-    sqlite_writer::create_table(&connection, &tableName, &columns)?;
-
-    // 5. Prepare the INSERT statement
-    let mut statement = sqlite_writer::prepare_insert(&connection, &tableName, &columns)?;
-
-    // 6. Begin initial transaction
-    sqlite_writer::begin_transaction(&connection)?;
-
-    // 7. Count total rows (for progress reporting)
-    let _ = window.emit(
-        "migration_progress",
-        ProgressEvent {
-            total_rows: 0,
-            processed_rows: 0,
-            row_count: 0,
-            batch_size: 0,
-            status: "counting_rows".to_string(),
-            message: None,
-        },
-    );
-
-    let total_rows = csv_reader::count_rows(&filePath)?;
-    let _ = window.emit(
-        "migration_progress",
-        ProgressEvent {
-            total_rows,
-            processed_rows: 0,
-            row_count: 0,
-            batch_size: 0,
-            status: "counted_rows".to_string(),
-            message: None,
-        },
-    );
-
-    // 8. Detect delimiter and create a CSV reader
-    let delimiter = csv_reader::detect_delimiter(&filePath)?;
-    let mut rdr = csv_reader::create_csv_reader(&filePath, delimiter)?;
-
-    // Reset cancellation flag at the start of migration
-    if let Some(flag) = CANCELLATION_REQUESTED.get() {
-        flag.store(false, Ordering::SeqCst);
-    }
-
-    // 9. Process CSV rows, inserting in batches
-    let mut processed_rows = 0;
-    let mut row_count = 0;
-    let mut last_logged = 0;
-
-    for result in rdr.records() {
-        // Check for user cancellation
-        if let Some(flag) = CANCELLATION_REQUESTED.get() {
-            if flag.load(Ordering::SeqCst) {
-                let _ = sqlite_writer::rollback_transaction(&connection); // Cleanup
-                let _ = window.emit(
-                    "migration_progress",
-                    ProgressEvent {
-                        total_rows,
-                        processed_rows,
-                        row_count,
-                        batch_size: 0,
-                        status: "cancelled".to_string(),
-                        message: Some("Migration cancelled by user".to_string()),
-                    },
-                );
-                // Reset the cancellation flag
-                flag.store(false, Ordering::SeqCst);
-                return Ok(());
-            }
-        }
-
-        row_count += 1;
-        processed_rows += 1;
-
-        // Log progress every 100,000 rows
-        if row_count - last_logged >= 100_000 {
-            // or just log to console if you wish
-            last_logged = row_count;
-        }
-
-        let record = match result {
-            Ok(r) => r,
-            Err(e) => {
-                let _ = sqlite_writer::rollback_transaction(&connection); // Cleanup
-                return Err(format!("Error processing row {}: {}", row_count, e));
-            }
-        };
-
-        // Insert the current record into SQLite
-        sqlite_writer::insert_record(&mut statement, &record)?;
-
-        // Commit the batch if we've reached the batch_size
-        if processed_rows % batch_size == 0 {
-            sqlite_writer::commit_and_begin_new_transaction(&connection)?;
-            let _ = window.emit(
-                "migration_progress",
-                ProgressEvent {
-                    total_rows,
-                    processed_rows,
-                    row_count,
-                    batch_size,
-                    status: "processing".to_string(),
-                    message: Some(format!(
-                        "Processed {} rows ({:.1}%)",
-                        processed_rows,
-                        (processed_rows as f64 / total_rows as f64) * 100.0
-                    )),
-                },
-            );
-        }
-    }
-
-    // Final commit
-    sqlite_writer::commit_transaction(&connection)?;
-
-    // Final progress event
-    let _ = window.emit(
-        "migration_progress",
-        ProgressEvent {
-            total_rows,
-            processed_rows,
-            row_count,
-            batch_size: 0,
-            status: "complete".to_string(),
-            message: None,
-        },
-    );
-
-    Ok(())
+    dialect: Option<DialectOptions>,
+    filePaths: Option<Vec<String>>,
+    sourceFileColumn: Option<bool>,
+    raggedRows: Option<RaggedRowPolicy>,
+    constraints: Option<Constraints>,
+    job_id: Option<String>,
+) -> Result<(), DatapumpError> {
+    migrate(
+        window,
+        csv_source(filePath, filePaths, dialect, sourceFileColumn),
+        SinkConfig::Sqlite { path: dbPath },
+        tableName,
+        batch_size,
+        Some(schema),
+        None,
+        raggedRows,
+        constraints,
+        job_id,
+    )
+    .await
+    .map(|_| ())
 }
 
+/// Read lines `offset..` of a text file in chunks of `chunkSize` lines, decoded
+/// to UTF-8 from the file's detected encoding. Given the file's `dialect`,
+/// the lines it skips are left out.
 #[tauri::command]
-pub async fn read_file_chunks(filePath: String, chunkSize: usize, offset: usize) -> Result<(Vec<String>, bool), String> {
-    use tokio::fs::File;
-    use tokio::io::{AsyncBufReadExt, BufReader};
-    use std::time::Instant;
-
-    let start = Instant::now();
-    println!("Starting to read file chunks from offset {}", offset);
-    
-    let file = File::open(&filePath).await.map_err(|e| e.to_string())?;
-    let reader = BufReader::new(file);
+pub async fn read_file_chunks(
+    filePath: String,
+    chunkSize: usize,
+    offset: usize,
+    dialect: Option<Dialect>,
+) -> Result<(Vec<String>, bool), DatapumpError> {
+    tokio::task::spawn_blocking(move || read_chunks(&filePath, chunkSize, offset, dialect)).await?
+}
+
+fn read_chunks(
+    file_path: &str,
+    chunk_size: usize,
+    offset: usize,
+    dialect: Option<Dialect>,
+) -> Result<(Vec<String>, bool), DatapumpError> {
+    use std::io::{BufRead, BufReader};
+
+    let reader: Box<dyn BufRead> = match dialect {
+        Some(dialect) => Box::new(BufReader::new(CsvInput::open(file_path, &dialect)?)),
+        None => {
+            let encoding = encoding::detect(file_path)?;
+            Box::new(BufReader::new(DecodedFile::open(file_path, encoding)?))
+        }
+    };
     let mut lines = reader.lines();
-    
+
     // Skip to offset
     for _ in 0..offset {
-        if lines.next_line().await.map_err(|e| e.to_string())?.is_none() {
+        if lines
+            .next()
+            .transpose()
+            .context("Failed to read file")?
+            .is_none()
+        {
             return Ok((Vec::new(), true));
         }
     }
-    
+
     let mut chunks = Vec::new();
-    let mut current_chunk = String::with_capacity(chunkSize * 100);
+    let mut current_chunk = String::with_capacity(chunk_size * 100);
     let mut line_count = 0;
     let mut total_lines = 0;
     let batch_size = 10;
-    let target_lines = chunkSize * batch_size;
-    
-    while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+    let target_lines = chunk_size * batch_size;
+
+    while let Some(line) = lines.next().transpose().context("Failed to read file")? {
         current_chunk.push_str(&line);
         current_chunk.push('\n');
         line_count += 1;
         total_lines += 1;
 
-        if line_count >= chunkSize {
+        if line_count >= chunk_size {
             chunks.push(current_chunk);
-            current_chunk = String::with_capacity(chunkSize * 100);
+            current_chunk = String::with_capacity(chunk_size * 100);
             line_count = 0;
         }
 
@@ -485,8 +442,11 @@ pub async fn read_file_chunks(filePath: String, chunkSize: usize, offset: usize)
     }
 
     // Check if there are more lines
-    let is_last_batch = lines.next_line().await.map_err(|e| e.to_string())?.is_none();
-    
-    println!("Read {} chunks in {:?}", chunks.len(), start.elapsed());
+    let is_last_batch = lines
+        .next()
+        .transpose()
+        .context("Failed to read file")?
+        .is_none();
+
     Ok((chunks, is_last_batch))
 }
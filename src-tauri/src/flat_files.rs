@@ -1,6 +1,8 @@
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path;
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::oneshot;
 
 #[tauri::command]
 pub async fn get_real_path(file_path: String) -> Result<String, String> {
@@ -19,3 +21,38 @@ pub async fn append_to_file(file_path: String, text: String) -> Result<(), Strin
     file.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
     Ok(())
 }
+
+#[tauri::command]
+pub async fn open_file_dialog(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let (tx, rx) = oneshot::channel();
+
+    app_handle.dialog().file().pick_file(move |path| {
+        let _ = tx.send(path.map(|p| p.to_string()));
+    });
+
+    match rx.await {
+        Ok(Some(path)) => Ok(path),
+        Ok(None) => Err("No file selected".to_string()),
+        Err(_) => Err("Failed to receive file path".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn save_file_dialog(app_handle: tauri::AppHandle, default_name: String) -> Result<String, String> {
+    let (tx, rx) = oneshot::channel();
+
+    app_handle
+        .dialog()
+        .file()
+        .set_file_name(default_name)
+        .add_filter("Job spec", &["toml", "json"])
+        .save_file(move |path| {
+            let _ = tx.send(path.map(|p| p.to_string()));
+        });
+
+    match rx.await {
+        Ok(Some(path)) => Ok(path),
+        Ok(None) => Err("No file selected".to_string()),
+        Err(_) => Err("Failed to receive file path".to_string()),
+    }
+}
@@ -1,7 +1,19 @@
+pub mod commands;
+pub mod flat_files;
+pub mod postgres;
+pub mod postgres_csv_import;
+pub mod sqlite_query;
+
+use tauri::Manager;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
+    .plugin(tauri_plugin_dialog::init())
     .setup(|app| {
+      if let Ok(dir) = app.path().app_data_dir() {
+        datapump_core::checkpoint::set_state_dir(dir.join("checkpoints"));
+      }
       if cfg!(debug_assertions) {
         app.handle().plugin(
           tauri_plugin_log::Builder::default()
@@ -11,6 +23,36 @@ pub fn run() {
       }
       Ok(())
     })
+    .invoke_handler(tauri::generate_handler![
+      postgres::execute_postgres_query,
+      sqlite_query::execute_sqlite_query,
+      flat_files::get_real_path,
+      flat_files::append_to_file,
+      flat_files::open_file_dialog,
+      flat_files::save_file_dialog,
+      commands::sniff_dialect,
+      commands::normalize_headers,
+      commands::get_csv_schema,
+      commands::detect_fixed_width,
+      commands::list_zip_entries,
+      commands::import_zip,
+      commands::csv_to_sqlite,
+      commands::csv_to_postgres,
+      commands::migrate,
+      commands::load_job_spec,
+      commands::validate_job_spec,
+      commands::save_job_spec,
+      commands::run_job_spec,
+      commands::cancel_migration,
+      commands::get_job_status,
+      commands::list_jobs,
+      commands::pause_migration,
+      commands::resume_migration,
+      commands::list_checkpoints,
+      commands::read_file_chunks,
+      postgres_csv_import::import_csv_to_postgres,
+      postgres_csv_import::check_postgres_table_exists
+    ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
@@ -0,0 +1,10 @@
+use datapump_core::error::DatapumpError;
+use datapump_core::query::{self, QueryResult};
+
+#[tauri::command]
+pub async fn execute_sqlite_query(
+    connection_string: String,
+    query: String,
+) -> Result<QueryResult, DatapumpError> {
+    query::execute_sqlite_query(&connection_string, &query)
+}
@@ -12,9 +12,10 @@ export default class MigrationCard {
     invoke = invoke;
     listen = listen;
     getCurrentWindow = getCurrentWindow;
-    totalRows = $state(0);
     processedRows = $state(0);
-    batchSize = $state(0);
+    bytesRead = $state(0);
+    totalBytes = $state(0);
+    percentComplete = $state(0);
     message = $state("");
     status = $state("idle");
     rowsPerSecond = $state(0);
@@ -22,6 +23,9 @@ export default class MigrationCard {
 
     cancellationRequested = $state(false);
     migrationInProgress = $state(false);
+    jobId = $state("");
+    // Id of an interrupted job whose checkpoint can be resumed
+    resumableJobId = $state("");
 
     sourceType = $state("csv_tsv");
     sourcePath = $state("");
@@ -41,6 +45,64 @@ export default class MigrationCard {
     tableName = $state("");
     selectedFile = $state<File | null>(null);
 
+    /** Rewrite header names in snake_case when creating columns. */
+    snakeCaseHeaders = $state(false);
+    /** Headers that had to be renamed to become column names, from the last schema analysis. */
+    renamedColumns = $state<ColumnName[]>([]);
+    /** Lines before the header row; detected when null. */
+    skipLines = $state<number | null>(null);
+    /** Lines to drop from the end of the file, such as totals. */
+    skipFooter = $state(0);
+    /** Lines starting with this prefix are ignored; none when empty. */
+    commentPrefix = $state("");
+    /** Decimal separator of numbers, "." or ","; detected per column when empty. */
+    decimalSeparator = $state("");
+    /** Record each row's file in a `_source_file` column; the source path may be a glob pattern. */
+    sourceFileColumn = $state(false);
+    /** Columns of a fixed-width source, as detected or edited; detected on import when empty. */
+    fixedWidthColumns = $state<FixedWidthColumn[]>([]);
+    /** Files in the chosen zip archive, from the last listing. */
+    archiveEntries = $state<ArchiveEntry[]>([]);
+    /** Names of the archive's files to import; all importable ones when empty. */
+    selectedMembers = $state<string[]>([]);
+    /** How the rows column types are inferred from are picked. */
+    sampleMethod = $state<Sample["method"]>("first");
+    /** Rows to infer column types from, unless the whole file is scanned. */
+    sampleRows = $state(1000);
+    /** What to do with rows with more or fewer fields than the header. */
+    raggedRows = $state<RaggedRows>("error");
+    /** CSV file rejected rows are appended to. */
+    rejectFile = $state("");
+    /** Whether a new table gets the NOT NULL columns and primary key the type sample suggests. */
+    applyConstraints = $state(false);
+    /** Constraints suggested by the last schema inference. */
+    suggestedConstraints = $state<SuggestedConstraints | null>(null);
+
+    /** What the user fixed about how the file is read; the backend detects the rest. */
+    dialectOptions(): DialectOptions {
+        return {
+            skip_lines: this.skipLines ?? undefined,
+            skip_footer: this.skipFooter || undefined,
+            comment: this.commentPrefix || undefined,
+            decimal: this.decimalSeparator || undefined,
+        };
+    }
+
+    /** Which rows column types are inferred from, as the backend takes it. */
+    sample(): Sample {
+        return this.sampleMethod === "full"
+            ? { method: "full" }
+            : { method: this.sampleMethod, rows: this.sampleRows };
+    }
+
+    /** How ragged rows are handled, as the backend takes it. */
+    raggedPolicy(): RaggedRowPolicy {
+        return {
+            ragged_rows: this.raggedRows,
+            reject_file: this.rejectFile || undefined,
+        };
+    }
+
     async analyzeSchema() {
         console.log('Analyzing schema for file:', this.sourcePath);
 
@@ -55,15 +117,33 @@ export default class MigrationCard {
             let firstBatch = true;
             const columnTypes: { [key: string]: string } = {};
             let totalRows = 0;
-            let fileDelimiter = ',';
-            let linebreak = '\n';
+
+            // Delimiter, quoting and header detection are done by the backend,
+            // so this analysis reads the file the same way the import will
+            const dialect = await this.invoke<Dialect>('sniff_dialect', {
+                filePath: this.sourcePath,
+                options: this.dialectOptions()
+            });
+            console.log('Sniffed dialect:', dialect);
+            const parseConfig = {
+                header: dialect.has_header,
+                delimiter: dialect.delimiter,
+                quoteChar: dialect.quote,
+                escapeChar: dialect.escape === "backslash" ? "\\" : dialect.quote,
+            };
+            // Without a header row Papa returns arrays; key them like the backend names them
+            const toRecords = (rows: any[]) =>
+                dialect.has_header
+                    ? rows
+                    : rows.map((row: string[]) => Object.fromEntries(row.map((value, i) => [`col_${i + 1}`, value])));
 
             while (hasMoreChunks) {
                 console.log(`Reading batch starting at offset ${offset}...`);
                 const [chunks, isLastBatch]: [string[], boolean] = await this.invoke<[string[], boolean]>('read_file_chunks', { 
                     filePath: this.sourcePath,
                     chunkSize: CHUNK_SIZE,
-                    offset
+                    offset,
+                    dialect
                 });
                 
                 if (!chunks || chunks.length === 0) {
@@ -80,20 +160,6 @@ export default class MigrationCard {
                     const firstChunkResults: { data: any[]; meta: Papa.ParseMeta } = await new Promise((resolve, reject) => {
                         console.log('Parsing first chunk to determine schema...');
                         
-                        // Get first line and analyze it
-                        const firstLine = chunks[0].split('\n')[0];
-                        console.log('Analyzing first line:', firstLine);
-                        
-                        // Count fields with different delimiters
-                        const testDelimiters = [',', '\t', ';'];
-                        const fieldCounts = testDelimiters.map(d => firstLine.split(d).length);
-                        console.log('Field counts by delimiter:', fieldCounts);
-                        
-                        // Choose delimiter that gives the most fields
-                        const maxFields = Math.max(...fieldCounts);
-                        const delimiter = testDelimiters[fieldCounts.indexOf(maxFields)];
-                        console.log(`Selected delimiter: "${delimiter}" (${maxFields} fields)`);
-                        
                         // Set up timeout protection
                         const timeout = setTimeout(() => {
                             console.error('Papa.parse timeout after 30 seconds');
@@ -101,8 +167,7 @@ export default class MigrationCard {
                         }, 30000);
                         
                         Papa.parse(chunks[0], {
-                            header: true,
-                            delimiter: delimiter,
+                            ...parseConfig,
                             skipEmptyLines: true,
                             complete: (results) => {
                                 clearTimeout(timeout);
@@ -111,8 +176,6 @@ export default class MigrationCard {
                                     fields: results.meta.fields?.length,
                                     delimiter: results.meta.delimiter
                                 });
-                                fileDelimiter = delimiter;
-                                linebreak = results.meta.linebreak;
                                 resolve(results);
                             },
                             error: (error) => {
@@ -125,7 +188,7 @@ export default class MigrationCard {
 
                     console.log('Analyzing column types from first chunk...');
                     if (firstChunkResults.data.length > 0) {
-                        const firstRow = firstChunkResults.data[0];
+                        const firstRow = toRecords(firstChunkResults.data)[0];
                         for (const column in firstRow) {
                             columnTypes[column] = this.detectType(firstRow[column]);
                         }
@@ -144,8 +207,7 @@ export default class MigrationCard {
                     
                     const chunkResults: { data: any[] } = await new Promise((resolve, reject) => {
                         Papa.parse(chunk, {
-                            header: true,
-                            delimiter: fileDelimiter,
+                            ...parseConfig,
                             skipEmptyLines: true,
                             complete: (results) => {
                                 console.log(`Chunk parsed: ${results.data.length} rows`);
@@ -211,10 +273,20 @@ export default class MigrationCard {
             }
 
             console.log('Final column types:', columnTypes);
+
+            // Headers become safe, unique column names; show the user which changed
+            const columns = await this.invoke<ColumnName[]>('normalize_headers', {
+                headers: Object.keys(columnTypes),
+                snakeCase: this.snakeCaseHeaders
+            });
+            this.renamedColumns = columns.filter((column) => column.original !== column.name);
+            const types = Object.values(columnTypes);
+            const fields: { [key: string]: string } = {};
+            columns.forEach((column, i) => (fields[column.name] = types[i]));
+
             return {
-                delimiter: fileDelimiter,
-                fields: columnTypes,
-                linebreak
+                dialect,
+                fields
             };
         } catch (error) {
             console.error('Error analyzing schema:', error);
@@ -251,6 +323,8 @@ export default class MigrationCard {
     }
 
     private initializeLocalStorage() {
+        const storedResumableJobId = localStorage.getItem("resumableJobId");
+        if (storedResumableJobId) this.resumableJobId = storedResumableJobId;
         const storedSourcePath = localStorage.getItem("sourcePath");
         if (storedSourcePath) this.sourcePath = storedSourcePath;
         const storedDestinationPath = localStorage.getItem("destinationPath");
@@ -312,11 +386,33 @@ export default class MigrationCard {
     });
     */
 
+    // Rates and estimates are computed by the backend; this only formats them.
+    applyProgress(event: ProgressEvent) {
+        this.status = event.stage;
+        this.message = event.message || "";
+        this.processedRows = event.processed_rows;
+        this.bytesRead = event.bytes_read;
+        this.totalBytes = event.total_bytes ?? 0;
+        if (this.totalBytes > 0) {
+            this.percentComplete = Math.min(100, Math.round((this.bytesRead / this.totalBytes) * 100));
+        }
+        this.rowsPerSecond = Math.round(event.rows_per_sec);
+
+        if (event.eta_secs != null) {
+            const hours = Math.floor(event.eta_secs / 3600);
+            const minutes = Math.floor((event.eta_secs % 3600) / 60);
+            const seconds = Math.floor(event.eta_secs % 60);
+            this.timeRemainingDisplay = `${hours}h ${minutes}m ${seconds}s`;
+        } else if (event.stage === "done") {
+            this.timeRemainingDisplay = "";
+        }
+    }
+
     async cancelMigration() {
         this.cancellationRequested = true;
         this.status = "cancelling";
         try {
-            await invoke("cancel_migration");
+            await invoke("cancel_migration", { jobId: this.jobId });
             this.status = "cancelled";
             this.message = "Migration cancelled by user";
             this.migrationInProgress = false;
@@ -328,74 +424,202 @@ export default class MigrationCard {
         }
     }
 
+    async pauseMigration() {
+        try {
+            await invoke("pause_migration", { jobId: this.jobId });
+            this.message = "Pausing after the current batch...";
+        } catch (error) {
+            console.error("Error pausing migration:", error);
+            this.message = "Failed to pause migration: " + errorMessage(error);
+        }
+    }
+
+    jobSpec() {
+        const destination = this.destinationType === "postgres"
+            ? { type: "postgres", connection_string: this.destinationPath }
+            : { type: "sqlite", path: this.destinationPath };
+        const fixedWidth = { type: "fixed_width", path: this.sourcePath, columns: this.fixedWidthColumns, ...this.dialectOptions() };
+        return {
+            source: this.sourceType === "fixed_width" ? fixedWidth : this.sourceFileColumn || /[*?[]/.test(this.sourcePath)
+                ? { type: "csv_files", paths: [this.sourcePath], source_file_column: this.sourceFileColumn, ...this.dialectOptions() }
+                : { type: "csv", path: this.sourcePath, ...this.dialectOptions() },
+            destination,
+            table_name: this.tableName,
+            batch_size: 10000,
+            mode: "append",
+            sample: this.sample(),
+            ...this.raggedPolicy()
+        };
+    }
+
+    /** Find the columns of the fixed-width file at the source path from its whitespace. */
+    async detectFixedWidth() {
+        try {
+            const layout = await invoke<FixedWidthLayout>("detect_fixed_width", {
+                filePath: this.sourcePath,
+                options: this.dialectOptions(),
+                snakeCase: this.snakeCaseHeaders
+            });
+            this.fixedWidthColumns = layout.columns;
+        } catch (error) {
+            console.error("Error detecting fixed-width columns:", error);
+            this.message = "Failed to detect columns: " + errorMessage(error);
+        }
+    }
+
+    /** Load the fixed-width file at the source path with `fixedWidthColumns`. */
+    async importFixedWidth() {
+        this.processedRows = 0;
+        this.percentComplete = 0;
+        this.status = "idle";
+        this.migrationInProgress = true;
+
+        const jobId = crypto.randomUUID();
+        this.jobId = jobId;
+        this.setResumableJob(jobId);
+        const unlisten = await listen<ProgressEvent>("migration_progress", (event) => {
+            if (event.payload.job_id && event.payload.job_id !== jobId) return;
+            this.applyProgress(event.payload);
+        });
+        try {
+            await invoke("run_job_spec", { window: getCurrentWindow(), spec: this.jobSpec(), jobId });
+            this.setResumableJob("");
+        } catch (error) {
+            if ((error as DatapumpError)?.code === "cancelled") {
+                return;
+            }
+            console.error("Error importing fixed-width file:", error);
+            this.status = "Error: " + errorMessage(error);
+        } finally {
+            unlisten();
+            this.migrationInProgress = false;
+        }
+    }
+
+    /** List the files in the zip archive at the source path, with their sniffed schemas. */
+    async listZipEntries() {
+        try {
+            this.archiveEntries = await invoke<ArchiveEntry[]>("list_zip_entries", {
+                path: this.sourcePath,
+                options: this.dialectOptions(),
+                snakeCase: this.snakeCaseHeaders
+            });
+            this.selectedMembers = this.archiveEntries
+                .filter((entry) => entry.schema !== null)
+                .map((entry) => entry.name);
+        } catch (error) {
+            console.error("Error listing zip archive:", error);
+            this.message = "Failed to read archive: " + errorMessage(error);
+        }
+    }
+
+    /** Import the selected files of the zip archive into a table each. */
+    async importZip() {
+        this.processedRows = 0;
+        this.percentComplete = 0;
+        this.status = "idle";
+        this.migrationInProgress = true;
+
+        // Each file is its own job, so follow whichever is running
+        const unlisten = await listen<ProgressEvent>("migration_progress", (event) => {
+            this.jobId = event.payload.job_id ?? this.jobId;
+            this.applyProgress(event.payload);
+        });
+        try {
+            const destination = this.destinationType === "postgres"
+                ? { type: "postgres", connection_string: this.destinationPath }
+                : { type: "sqlite", path: this.destinationPath };
+            const imported = await invoke<MemberImport[]>("import_zip", {
+                window: getCurrentWindow(),
+                path: this.sourcePath,
+                members: this.selectedMembers,
+                destination,
+                options: this.dialectOptions(),
+                snakeCase: this.snakeCaseHeaders,
+                ragged: this.raggedPolicy()
+            });
+            this.message = imported.map((m) => `${m.member} → ${m.table_name}: ${m.rows} rows`).join("\n");
+        } catch (error) {
+            if ((error as DatapumpError)?.code === "cancelled") {
+                return;
+            }
+            console.error("Error importing zip archive:", error);
+            this.status = "Error: " + errorMessage(error);
+        } finally {
+            unlisten();
+            this.migrationInProgress = false;
+        }
+    }
+
+    async exportJobSpec() {
+        try {
+            const path = await invoke<string>("save_file_dialog", { defaultName: `${this.tableName || "migration"}.toml` });
+            await invoke("save_job_spec", { spec: this.jobSpec(), path });
+            this.message = `Job spec saved to ${path}`;
+        } catch (error) {
+            console.error("Error exporting job spec:", error);
+            this.message = "Failed to export job spec: " + errorMessage(error);
+        }
+    }
+
     async startMigration() {
         // Reset state variables
-        this.totalRows = 0;
         this.processedRows = 0;
-        this.batchSize = 0;
+        this.bytesRead = 0;
+        this.totalBytes = 0;
+        this.percentComplete = 0;
         this.message = "";
         this.status = "idle";
         this.timeRemainingDisplay = "";
         this.cancellationRequested = false;
         this.migrationInProgress = true;
 
-        // Reset cancellation flag in Rust
-        await invoke("reset_cancellation");
+        // Each migration gets its own job id so it can be cancelled on its own
+        const jobId = crypto.randomUUID();
+        this.jobId = jobId;
+        // Kept until the job completes, so it can be resumed even after a crash
+        this.setResumableJob(jobId);
 
-        let ts = +new Date();
         // Setup event listener
         const unlisten = await listen<ProgressEvent>("migration_progress", (event) => {
+            if (event.payload.job_id && event.payload.job_id !== jobId) return;
             if (this.cancellationRequested) return;
-
-            this.processedRows = event.payload.processed_rows;
-            this.totalRows = event.payload.total_rows;
-            this.batchSize = event.payload.batch_size;
-            this.status = event.payload.status;
-            this.message = event.payload.message || "";
-            const elapsed = (+new Date() - ts) / 1000;
-            const rps = elapsed > 0 ? this.processedRows / elapsed : 0;
-            this.rowsPerSecond = Math.round(rps);
-            
-            // Calculate time remaining only if we have a valid rate and total rows
-            if (rps > 0 && this.totalRows > 0 && this.processedRows < this.totalRows) {
-                let timeRemaining = (this.totalRows - this.processedRows) / rps;
-                const hours = Math.floor(timeRemaining / 3600);
-                const minutes = Math.floor((timeRemaining % 3600) / 60);
-                const seconds = Math.floor(timeRemaining % 60);
-                this.timeRemainingDisplay = `${hours}h ${minutes}m ${seconds}s`;
-            }
-            
-            if (this.status === "parsing_schema_complete" || this.status === "counted_rows") {
-                ts = +new Date();
-            }
+            this.applyProgress(event.payload);
         });
 
         try {
-            const schema = await invoke("get_csv_schema", { filePath: this.sourcePath, tableName: this.tableName });
-
-            if (typeof schema !== "string") {
-                throw new Error("Invalid schema format: expected string");
-            }
-
-            const schemaParts = schema.split(",");
-
-            // Validate schema format
-            if (!schemaParts.every((part) => part.includes(":"))) {
-                throw new Error("Invalid schema format: each part should be in 'name:type' format");
-            }
+            const { schema, constraints } = await invoke<CsvSchema>("get_csv_schema", {
+                filePath: this.sourcePath,
+                options: this.dialectOptions(),
+                snakeCase: this.snakeCaseHeaders,
+                sample: this.sample()
+            });
+            this.renamedColumns = schema.columns.filter((column) => column.original !== null && column.original !== column.name);
+            this.suggestedConstraints = constraints;
 
             const window = getCurrentWindow();
             const result = await invoke(`csv_to_${this.destinationType}`, {
                 window,
                 filePath: this.sourcePath,
                 batchSize: 10000,
-                schema: schema,
+                schema,
                 tableName: this.tableName,
-                dbPath: this.destinationPath
+                dbPath: this.destinationPath,
+                // Read the file exactly as the schema was inferred from it
+                dialect: schema.dialect,
+                sourceFileColumn: this.sourceFileColumn,
+                raggedRows: this.raggedPolicy(),
+                constraints: this.applyConstraints ? acceptedConstraints(constraints) : undefined,
+                jobId
             });
+            this.setResumableJob("");
         } catch (error) {
+            // Don't treat cancellation as an error
+            if ((error as DatapumpError)?.code === "cancelled") {
+                return;
+            }
             console.error("Error during CSV to SQLite migration:", error);
-            this.status = "Error: " + (error as string) || "ERROR";
+            this.status = "Error: " + errorMessage(error);
             this.migrationInProgress = false;
             throw error;
         } finally {
@@ -405,11 +629,65 @@ export default class MigrationCard {
         }
     }
 
+    /** Resume the paused migration, or continue the last interrupted one from its checkpoint. */
+    async resumeMigration() {
+        if (this.status === "paused") {
+            try {
+                // The command that started the job is still waiting for it to finish
+                await invoke("resume_migration", { window: getCurrentWindow(), jobId: this.jobId });
+            } catch (error) {
+                console.error("Error resuming migration:", error);
+                this.message = "Failed to resume migration: " + errorMessage(error);
+            }
+            return;
+        }
+
+        const jobId = this.resumableJobId;
+        if (!jobId) return;
+
+        this.message = "";
+        this.status = "idle";
+        this.timeRemainingDisplay = "";
+        this.cancellationRequested = false;
+        this.migrationInProgress = true;
+        this.jobId = jobId;
+
+        const unlisten = await listen<ProgressEvent>("migration_progress", (event) => {
+            if (event.payload.job_id && event.payload.job_id !== jobId) return;
+            if (this.cancellationRequested) return;
+            this.applyProgress(event.payload);
+        });
+
+        try {
+            await invoke("resume_migration", { window: getCurrentWindow(), jobId });
+            this.setResumableJob("");
+        } catch (error) {
+            if ((error as DatapumpError)?.code === "cancelled") {
+                return;
+            }
+            console.error("Error resuming migration:", error);
+            this.status = "Error: " + errorMessage(error);
+        } finally {
+            unlisten();
+            this.migrationInProgress = false;
+        }
+    }
+
+    private setResumableJob(jobId: string) {
+        this.resumableJobId = jobId;
+        if (jobId) {
+            localStorage.setItem("resumableJobId", jobId);
+        } else {
+            localStorage.removeItem("resumableJobId");
+        }
+    }
+
     async startMigration2() {
         // Reset state variables
-        this.totalRows = 0;
         this.processedRows = 0;
-        this.batchSize = 0;
+        this.bytesRead = 0;
+        this.totalBytes = 0;
+        this.percentComplete = 0;
         this.message = "";
         this.status = "idle";
         this.timeRemainingDisplay = "";
@@ -436,41 +714,21 @@ export default class MigrationCard {
             console.log("Analyzing schema...");
             const schemaInfo = await this.analyzeSchema();
             console.log("Schema Analysis Results:");
-            console.log("Field Delimiter:", schemaInfo.delimiter);
-            console.log("Line Break:", schemaInfo.linebreak);
+            console.log("Dialect:", schemaInfo.dialect);
             console.log("Fields:", schemaInfo.fields);
 
-            // Reset cancellation flag in Rust
-            console.log("Resetting cancellation flag...");
-            await invoke("reset_cancellation");
+            // Each migration gets its own job id so it can be cancelled on its own
+            const jobId = crypto.randomUUID();
+            this.jobId = jobId;
+            // Batches are checkpointed, so a failed import can be resumed
+            this.setResumableJob(jobId);
 
-            let ts = +new Date();
             // Setup event listener
             console.log("Setting up progress listener...");
             const unlisten = await listen<ProgressEvent>("migration_progress", (event) => {
+                if (event.payload.job_id && event.payload.job_id !== jobId) return;
                 if (this.cancellationRequested) return;
-
-                this.processedRows = event.payload.processed_rows;
-                this.totalRows = event.payload.total_rows;
-                this.batchSize = event.payload.batch_size;
-                this.status = event.payload.status;
-                this.message = event.payload.message || "";
-                const elapsed = (+new Date() - ts) / 1000;
-                const rps = elapsed > 0 ? this.processedRows / elapsed : 0;
-                this.rowsPerSecond = Math.round(rps);
-                
-                // Calculate time remaining only if we have a valid rate and total rows
-                if (rps > 0 && this.totalRows > 0 && this.processedRows < this.totalRows) {
-                    let timeRemaining = (this.totalRows - this.processedRows) / rps;
-                    const hours = Math.floor(timeRemaining / 3600);
-                    const minutes = Math.floor((timeRemaining % 3600) / 60);
-                    const seconds = Math.floor(timeRemaining % 60);
-                    this.timeRemainingDisplay = `${hours}h ${minutes}m ${seconds}s`;
-                }
-                
-                if (this.status === "parsing_schema_complete" || this.status === "counted_rows") {
-                    ts = +new Date();
-                }
+                this.applyProgress(event.payload);
             });
 
             try {
@@ -486,21 +744,23 @@ export default class MigrationCard {
                     connectionString,
                     pathToFile: this.sourcePath,
                     tableName: this.tableName,
-                    delimiter: schemaInfo.delimiter,
-                    linebreak: schemaInfo.linebreak,
-                    fields
+                    dialect: schemaInfo.dialect,
+                    fields,
+                    ragged: this.raggedPolicy(),
+                    jobId
                 });
+                this.setResumableJob("");
                 
                 console.log("Import completed successfully");
             } catch (error) {
                 // Don't treat cancellation as an error
-                if (error === "Migration cancelled by user") {
+                if ((error as DatapumpError)?.code === "cancelled") {
                     console.log("Migration cancelled by user");
                     return;
                 }
                 
                 console.error("Error during CSV to PostgreSQL migration:", error);
-                this.status = "Error: " + (error as string) || "ERROR";
+                this.status = "Error: " + errorMessage(error);
                 throw error;
             } finally {
                 console.log("Cleaning up...");
@@ -509,7 +769,7 @@ export default class MigrationCard {
             }
         } catch (error) {
             console.error("Error during migration process:", error);
-            this.status = "Error: " + (error as string) || "ERROR";
+            this.status = "Error: " + errorMessage(error);
             this.migrationInProgress = false;
             throw error;
         }
@@ -523,11 +783,226 @@ export default class MigrationCard {
     }
 }
 
+type Stage =
+    | "connecting"
+    | "sniffing"
+    | "creating_table"
+    | "loading"
+    | "paused"
+    | "finalizing"
+    | "verifying"
+    | "done"
+    | "failed"
+    | "cancelled";
+
+/** A column of a fixed-width file. */
+interface FixedWidthColumn {
+    name: string;
+    /** Position of the first character, counting from 1. */
+    start: number;
+    /** Width in characters. */
+    length: number;
+    /** e.g. "integer"; inferred when omitted. */
+    type?: string;
+}
+
+/** The columns of a fixed-width file and how it is read, from `detect_fixed_width`. */
+interface FixedWidthLayout {
+    columns: FixedWidthColumn[];
+    dialect: Dialect;
+}
+
+/** A file inside a zip archive, as listed by `list_zip_entries`. */
+interface ArchiveEntry {
+    /** Name in the archive, including folders. */
+    name: string;
+    /** "archive.zip!/name", usable as a source path. */
+    path: string;
+    size: number;
+    compressed_size: number;
+    /** Table the file is imported into. */
+    table_name: string;
+    /** Null when the file is not in a supported format; see `error`. */
+    schema: CsvSchema | null;
+    error: string | null;
+}
+
+/** A file of a zip archive loaded by `import_zip`. */
+interface MemberImport {
+    member: string;
+    table_name: string;
+    rows: number;
+}
+
+/** How a delimited file is written, as sniffed by the backend. */
+interface Dialect {
+    /** e.g. "UTF-8" or "windows-1252" */
+    encoding: string;
+    delimiter: string;
+    quote: string;
+    escape: "doubled" | "backslash";
+    terminator: "lf" | "crlf" | "cr";
+    has_header: boolean;
+    /** Lines before the header row, such as a report title */
+    skip_lines: number;
+    /** Lines after the last row, such as totals */
+    skip_footer: number;
+    /** Prefix of comment lines, e.g. "#" */
+    comment?: string;
+    /** Regular expression matching comment lines */
+    comment_pattern?: string;
+    /** Decimal separator of numbers, when not detected per column */
+    decimal?: string;
+}
+
+/** What is fixed about a file's dialect before sniffing; omitted fields are detected. */
+interface DialectOptions {
+    encoding?: string;
+    delimiter?: string;
+    has_header?: boolean;
+    skip_lines?: number;
+    skip_footer?: number;
+    comment?: string;
+    comment_pattern?: string;
+    decimal?: string;
+}
+
+/** What to do with a row whose number of fields differs from the header's. */
+type RaggedRows = "error" | "pad" | "truncate" | "pad_and_truncate" | "reject";
+
+interface RaggedRowPolicy {
+    ragged_rows: RaggedRows;
+    /** Required to reject rows */
+    reject_file?: string;
+}
+
+interface RaggedRowCounts {
+    padded: number;
+    truncated: number;
+    rejected: number;
+}
+
+/** A header as written in the file and the column name it became. */
+interface ColumnName {
+    /** null when the file has no header row */
+    original: string | null;
+    name: string;
+}
+
+/** One column of a `Schema`. */
+interface SchemaColumn {
+    /** The header as written; null when the file has no header row */
+    original: string | null;
+    name: string;
+    /** Logical type such as "integer" or "date", without its format. */
+    type: string;
+    /** Whether the sample had empty values; NOT NULL comes from constraints. */
+    nullable: boolean;
+    /** How numbers, dates and times are written, e.g. "1.234,56" or "%d/%m/%Y". */
+    format?: string;
+    /** A few distinct values from the sample. */
+    samples?: string[];
+    /** How sure inference is of the type, from 0 to 1. */
+    confidence: number;
+}
+
+/** Columns a file is loaded as, and the dialect it was read in. */
+interface Schema {
+    columns: SchemaColumn[];
+    dialect?: Dialect;
+}
+
+/** Result of `get_csv_schema`. */
+interface CsvSchema {
+    schema: Schema;
+    /** Encoding the file was read with, e.g. "UTF-8" or "windows-1252". */
+    encoding: string;
+    /** How the file is compressed; it is decompressed as it is read. */
+    compression: "none" | "gzip" | "zstd" | "bzip2" | "xz" | "zip";
+    sample: Sample;
+    /** Rows the column types were inferred from. */
+    rows_sampled: number;
+    /** Empty and distinct values per column, in schema order. */
+    stats: ColumnStats[];
+    constraints: SuggestedConstraints;
+}
+
+/** How many of a column's sampled values were empty and how many distinct. */
+interface ColumnStats {
+    name: string;
+    nulls: number;
+    distinct: number;
+    /** false when `distinct` is an estimate */
+    exact: boolean;
+}
+
+/** Constraints the sampled values suggest, applied only if the user accepts them. */
+interface SuggestedConstraints {
+    not_null: string[];
+    /** Single columns first, then pairs. */
+    primary_keys: { columns: string[]; exact: boolean }[];
+}
+
+/** Constraints a new table is created with. */
+interface Constraints {
+    not_null?: string[];
+    primary_key?: string[];
+}
+
+/** The suggested NOT NULL columns and the first primary key candidate. */
+function acceptedConstraints(suggested: SuggestedConstraints): Constraints {
+    return {
+        not_null: suggested.not_null,
+        primary_key: suggested.primary_keys[0]?.columns ?? [],
+    };
+}
+
+/** Which rows column types are inferred from. */
+type Sample =
+    | { method: "first"; rows: number }
+    | { method: "reservoir"; rows: number }
+    | { method: "full" };
+
+/** Error returned by the backend commands; `code` is stable, the rest is context. */
+interface DatapumpError {
+    code:
+        | "io"
+        | "connection"
+        | "auth"
+        | "parse"
+        | "type_coercion"
+        | "constraint"
+        | "database"
+        | "invalid_input"
+        | "internal"
+        | "cancelled";
+    message: string;
+    line?: number;
+    column?: string;
+    value?: string;
+    constraint?: string;
+    sqlstate?: string;
+    detail?: string;
+}
+
+function errorMessage(error: unknown): string {
+    const e = error as DatapumpError;
+    if (e && typeof e === "object" && "message" in e) {
+        return e.detail ? `${e.message} (${e.detail})` : e.message;
+    }
+    return String(error);
+}
+
 interface ProgressEvent {
-    total_rows: number;
+    job_id?: string;
+    stage: Stage;
     processed_rows: number;
-    row_count: number;
-    batch_size: number;
-    status: string;
+    bytes_read: number;
+    total_bytes?: number;
+    rows_per_sec: number;
+    eta_secs?: number;
+    elapsed_secs: number;
+    /** Ragged rows fitted or rejected, once the job is done. */
+    ragged_rows?: RaggedRowCounts;
     message?: string;
 }
\ No newline at end of file
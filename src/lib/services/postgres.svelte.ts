@@ -5,6 +5,6 @@ export async function executePostgresQuery(connectionString: string, sqlQuery: s
         const data = await invoke("execute_postgres_query", { connectionString, query: sqlQuery });
         return { data, error: null };
     } catch (e: any) {
-        return { data: null, error: e.toString() };
+        return { data: null, error: e?.message ?? e.toString() };
     }
 }
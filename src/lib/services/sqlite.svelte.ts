@@ -5,6 +5,6 @@ export async function executeSqliteQuery(connectionString: string, sqlQuery: str
         const data = await invoke("execute_sqlite_query", { connectionString, query: sqlQuery });
         return { data, error: null };
     } catch (e: any) {
-        return { data: null, error: e.toString() };
+        return { data: null, error: e?.message ?? e.toString() };
     }
 }
\ No newline at end of file